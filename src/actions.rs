@@ -0,0 +1,130 @@
+//! 起動中のCooを外から操作するためのアクションです。
+//! `coo --action switch-view=cards`や`gapplication action com.varwww.coo switch-view "'cards'"`で呼び出せます。
+use gio::prelude::*;
+use gtk::prelude::*;
+use std::path;
+
+use coo::i18n;
+
+use crate::main_window::MainWindow;
+use crate::Coo;
+
+fn find_main_window(application: &Coo) -> Option<MainWindow> {
+    application
+        .windows()
+        .into_iter()
+        .find_map(|window| window.downcast::<MainWindow>().ok())
+}
+
+fn string_parameter(parameter: Option<&glib::Variant>) -> Option<String> {
+    parameter.and_then(|parameter| parameter.get::<String>())
+}
+
+fn add_action<F: Fn(&MainWindow, Option<String>) + 'static>(
+    application: &Coo,
+    name: &str,
+    parameter_type: Option<&glib::VariantTy>,
+    handler: F,
+) {
+    let action = gio::SimpleAction::new(name, parameter_type);
+    action.connect_activate(glib::clone!(@weak application => move |action, parameter| {
+        log::debug!("アクション: {}, 引数: {:?}", action.name(), parameter);
+        match find_main_window(&application) {
+            Some(main_window) => {
+                handler(&main_window, string_parameter(parameter));
+                main_window.present();
+            }
+            None => log::warn!("メインウィンドウが無いので、{}を実行できません。", action.name()),
+        }
+    }));
+    application.add_action(&action);
+}
+
+pub fn register(application: &Coo) {
+    let string_type = String::static_variant_type();
+    let string = Some(string_type.as_ref());
+
+    add_action(application, "switch-view", string, |main_window, id| {
+        if let Some(id) = id {
+            main_window.show_view(&id);
+        }
+    });
+    add_action(
+        application,
+        "goto-date",
+        string,
+        |main_window, date| match date
+            .as_deref()
+            .map(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        {
+            Some(Ok(date)) => main_window.goto_date(date),
+            _ => log::warn!("日付はYYYY-MM-DDの形式で指定してください: {:?}", date),
+        },
+    );
+    add_action(application, "open-file", string, |main_window, path| {
+        if let Some(path) = path {
+            main_window.open_file(&coo::libs::expand_path(&path));
+        }
+    });
+    add_action(application, "add-card", string, |main_window, text| {
+        if let Some(text) = text {
+            main_window.add_card(&text);
+        }
+    });
+    add_action(application, "reload-config", None, |main_window, _| {
+        main_window.reload_config();
+    });
+}
+
+/// `open-file`の相対パスは、起動中のCooではなく、`coo --action`を実行したディレクトリを基準にします。
+fn absolute_path(path: &str) -> String {
+    let path = path::PathBuf::from(coo::libs::expand_path(path));
+    if path.is_absolute() {
+        return path.display().to_string();
+    }
+    std::env::current_dir()
+        .map(|dir| dir.join(&path))
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// `--action`の値(`名前`または`名前=引数`)を分けます。
+/// 登録した後で呼び、アクションが無いときや引数の有無が合わないときはエラーを返します。
+pub fn parse_action(
+    application: &Coo,
+    spec: &str,
+) -> Result<(String, Option<glib::Variant>), String> {
+    let (name, parameter) = match spec.split_once('=') {
+        Some((name, parameter)) => (name, Some(parameter)),
+        None => (spec, None),
+    };
+    if !application.has_action(name) {
+        return Err(i18n::format("不明なアクションです: {}", &[&name]));
+    }
+
+    let parameter = match (application.action_parameter_type(name), parameter) {
+        (None, None) => None,
+        (None, Some(_)) => {
+            return Err(i18n::format("{}は引数を取りません。", &[&name]));
+        }
+        (Some(_), None) => {
+            return Err(i18n::format(
+                "{0}には引数が必要です。`{0}=<引数>`のように指定してください。",
+                &[&name],
+            ));
+        }
+        (Some(parameter_type), Some(_)) if *parameter_type != *String::static_variant_type() => {
+            return Err(i18n::format(
+                "{}の引数の型({})には対応していません。",
+                &[&name, &parameter_type.to_str()],
+            ));
+        }
+        (Some(_), Some(parameter)) if name == "open-file" => {
+            Some(absolute_path(parameter).to_variant())
+        }
+        (Some(_), Some(parameter)) => Some(parameter.to_variant()),
+    };
+
+    Ok((name.to_string(), parameter))
+}
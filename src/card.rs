@@ -0,0 +1,1197 @@
+//! assorted_cardのカードと、日ごとのカードを保存するファイル(root/YYYY/YYYY-MM/YYYY-MM-DD.toml)を扱います。
+use chrono::prelude::*;
+use std::collections;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path;
+
+use crate::i18n;
+
+/// カードの種類を表すキーです。ビューの設定の`keys`で変えられます。
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CardKey {
+    /// ファイルに保存する、キーそのものです。
+    pub symbol: String,
+    /// キーを選ぶときに表示する説明です。
+    #[serde(default)]
+    pub label: Option<String>,
+    /// このキーのカードの背景色です。
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub task: Option<Task>,
+}
+
+/// 作業として数えるキーの、作業の状態です。
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Task {
+    /// 終わっていない作業です。前の日から持ち越せます。
+    Open,
+    /// 終わった作業です。このキーにすると`completed_at`を記録します。
+    Done,
+}
+
+impl CardKey {
+    fn new(symbol: &str, task: Option<Task>) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            label: None,
+            color: None,
+            task,
+        }
+    }
+}
+
+/// ビューで使うキーの一覧です。ひとつ以上あり、同じ`symbol`はありません。
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(try_from = "Vec<CardKey>")]
+pub struct CardKeys(Vec<CardKey>);
+
+impl Default for CardKeys {
+    fn default() -> Self {
+        Self(vec![
+            CardKey::new("📝", None),
+            CardKey::new("🔲", Some(Task::Open)),
+            CardKey::new("✅", Some(Task::Done)),
+            CardKey::new("📅", None),
+        ])
+    }
+}
+
+impl std::convert::TryFrom<Vec<CardKey>> for CardKeys {
+    type Error = String;
+
+    fn try_from(keys: Vec<CardKey>) -> Result<Self, Self::Error> {
+        if keys.is_empty() {
+            return Err(i18n::tr("キーをひとつ以上指定してください。").to_string());
+        }
+        for (i, key) in keys.iter().enumerate() {
+            if key.symbol.is_empty() {
+                return Err(i18n::tr("キーが空です。").to_string());
+            }
+            if keys[..i].iter().any(|other| other.symbol == key.symbol) {
+                return Err(i18n::format("キーが重複しています: {}", &[&key.symbol]));
+            }
+            if let Some(color) = &key.color {
+                if color.parse::<gdk::RGBA>().is_err() {
+                    return Err(i18n::format("色を解釈できません: {}", &[color]));
+                }
+            }
+        }
+
+        Ok(Self(keys))
+    }
+}
+
+impl CardKeys {
+    pub fn iter(&self) -> impl Iterator<Item = &CardKey> {
+        self.0.iter()
+    }
+
+    /// 新しいカードに使う、最初のキーです。
+    pub fn first(&self) -> &CardKey {
+        &self.0[0]
+    }
+
+    pub fn find(&self, symbol: &str) -> Option<&CardKey> {
+        self.0.iter().find(|key| key.symbol == symbol)
+    }
+
+    /// `symbol`の作業の状態を返します。知らないキーや作業でないキーなら`None`です。
+    pub fn task(&self, symbol: &str) -> Option<Task> {
+        self.find(symbol).and_then(|key| key.task)
+    }
+
+    /// 作業を終わったことにするときに使う、最初の`done`のキーです。
+    pub fn done(&self) -> Option<&CardKey> {
+        self.0.iter().find(|key| key.task == Some(Task::Done))
+    }
+
+    pub fn symbols(&self) -> Vec<&str> {
+        self.0.iter().map(|key| key.symbol.as_str()).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Card {
+    pub id: uuid::Uuid,
+    pub key: String,
+    pub text: String,
+    pub created_at: chrono::DateTime<chrono::Local>,
+    pub updated_at: chrono::DateTime<chrono::Local>,
+    /// キーが`done`のキー(既定では✅)になった日時です。`done`でないキーに変えたら消します。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<chrono::DateTime<chrono::Local>>,
+    /// 前の日から持ち越したカードであれば、その元です。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carried_from: Option<CarriedFrom>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CarriedFrom {
+    /// 最初に書いた日です。何度持ち越しても変わりません。
+    pub date: chrono::NaiveDate,
+    /// 持ち越す前のカードのIDです。移した場合は、このカードと同じIDです。
+    pub id: uuid::Uuid,
+}
+
+/// 前の日の終わっていない作業のカードを、今日へ移すか写すかです。
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CarryOverMode {
+    /// 前の日からは消します。
+    Move,
+    /// 前の日にも残し、写したカードに元のカードのIDを記録します。
+    Copy,
+}
+
+impl Default for CarryOverMode {
+    fn default() -> Self {
+        CarryOverMode::Move
+    }
+}
+
+impl Card {
+    pub fn new(key: String, text: String, keys: &CardKeys) -> Self {
+        let now = chrono::Local::now();
+        Self {
+            id: uuid::Uuid::new_v4(),
+            completed_at: if keys.task(&key) == Some(Task::Done) {
+                Some(now)
+            } else {
+                None
+            },
+            key,
+            text,
+            created_at: now,
+            updated_at: now,
+            carried_from: None,
+        }
+    }
+
+    /// 持ち越したカードであれば、最初に書いた日から`today`までの日数を返します。
+    pub fn pending_days(&self, today: chrono::NaiveDate) -> Option<i64> {
+        self.carried_from
+            .as_ref()
+            .map(|carried_from| (today - carried_from.date).num_days())
+    }
+
+    /// キーと本文を書き換えます。変わっていれば`updated_at`を、キーが変わっていれば`completed_at`も更新します。
+    pub fn update(&mut self, key: &str, text: &str, keys: &CardKeys) {
+        if self.key == key && self.text == text {
+            return;
+        }
+        let now = chrono::Local::now();
+        if self.key != key {
+            self.completed_at = if keys.task(key) == Some(Task::Done) {
+                Some(now)
+            } else {
+                None
+            };
+            self.key = key.to_string();
+        }
+        self.text = text.to_string();
+        self.updated_at = now;
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct DailyBucket {
+    pub version: usize,
+    pub date: chrono::NaiveDate,
+    pub cards: Vec<Card>,
+}
+
+impl DailyBucket {
+    pub fn new(date: chrono::NaiveDate, cards: Vec<Card>) -> Self {
+        Self {
+            version: current_version(),
+            date,
+            cards,
+        }
+    }
+}
+
+/// 週ごとのメモです。日のファイルと同じディレクトリに`YYYY-Www.toml`として保存します。
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct WeeklyBucket {
+    pub version: usize,
+    /// ISO 8601の週の年です。
+    pub year: i32,
+    pub week: u32,
+    pub cards: Vec<Card>,
+}
+
+impl WeeklyBucket {
+    pub fn new(week: chrono::IsoWeek, cards: Vec<Card>) -> Self {
+        Self {
+            version: current_version(),
+            year: week.year(),
+            week: week.week(),
+            cards,
+        }
+    }
+}
+
+/// 空のカードを除いて、同じカードが同じ順に並んでいるかを返します。空のカードは入力欄なので、比べません。
+pub fn same_cards(a: &[Card], b: &[Card]) -> bool {
+    let non_empty = |cards: &[Card]| {
+        cards
+            .iter()
+            .filter(|card| !card.text.is_empty())
+            .cloned()
+            .collect::<Vec<Card>>()
+    };
+    non_empty(a) == non_empty(b)
+}
+
+/// `theirs`の後に、`theirs`に無い`mine`のカードを並べます。両方にあるカードは、後から更新された方を使います。
+pub fn merge_cards(theirs: &[Card], mine: &[Card]) -> Vec<Card> {
+    let mut merged = theirs
+        .iter()
+        .map(|card| match mine.iter().find(|mine| mine.id == card.id) {
+            Some(mine) if mine.updated_at > card.updated_at => mine.clone(),
+            _ => card.clone(),
+        })
+        .collect::<Vec<Card>>();
+    for card in mine {
+        if !card.text.is_empty() && !theirs.iter().any(|theirs| theirs.id == card.id) {
+            merged.push(card.clone());
+        }
+    }
+    merged
+}
+
+/// カードのファイルを読み書きできなかったことを表します。
+#[derive(Debug)]
+pub enum StorageError {
+    Io {
+        path: String,
+        error: io::Error,
+    },
+    Parse {
+        path: String,
+        error: toml::de::Error,
+    },
+    /// TOMLとしては読めたものの、`key`の形式が違います。
+    Invalid {
+        path: String,
+        key: String,
+    },
+    /// このCooより新しいCooで保存されたファイルです。
+    UnsupportedVersion {
+        path: String,
+        version: usize,
+    },
+    /// バージョン`version`から次のバージョンへの変換に失敗しました。
+    Migration {
+        path: String,
+        version: usize,
+        message: String,
+    },
+    Serialize(toml::ser::Error),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            StorageError::Io { path, error } => {
+                i18n::format("{}: 読み書きできません。{}", &[path, error])
+            }
+            StorageError::Parse { path, error } => {
+                i18n::format("{}: 解釈できません。{}", &[path, error])
+            }
+            StorageError::Invalid { path, key } => {
+                i18n::format("{}: {}の形式が正しくありません。", &[path, key])
+            }
+            StorageError::UnsupportedVersion { path, version } => i18n::format(
+                "{}: バージョン{}の形式には対応していません。Cooを更新してください。",
+                &[path, version],
+            ),
+            StorageError::Migration {
+                path,
+                version,
+                message,
+            } => i18n::format(
+                "{}: バージョン{}から変換できません。{}",
+                &[path, version, message],
+            ),
+            StorageError::Serialize(error) => {
+                i18n::format("カードを保存する形式に変換できません。{}", &[error])
+            }
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+fn bucket_dir(root: &str, date: chrono::NaiveDate) -> String {
+    crate::libs::expand_path(&format!("{}/{}", root, &date.format("%Y/%Y-%m")))
+}
+
+pub fn bucket_file_path(root: &str, date: chrono::NaiveDate) -> String {
+    format!(
+        "{}/{}.toml",
+        bucket_dir(root, date),
+        &date.format("%Y-%m-%d")
+    )
+}
+
+/// ファイルの形式を変えるときは、変換する関数をここに足します。
+/// `MIGRATIONS[i]`は、バージョン`i + 1`のファイルの内容をバージョン`i + 2`の形式に書き換えます。
+type Migration = fn(&mut toml::value::Table) -> Result<(), String>;
+static MIGRATIONS: &[Migration] = &[add_card_ids];
+
+/// `table`の日付です。週のメモなら、その週の月曜日にします。
+fn bucket_date(table: &toml::value::Table) -> Option<chrono::NaiveDate> {
+    if let Some(date) = table.get("date") {
+        return chrono::NaiveDate::parse_from_str(date.as_str()?, "%Y-%m-%d").ok();
+    }
+    let year = table.get("year")?.as_integer()?;
+    let week = table.get("week")?.as_integer()?;
+    chrono::NaiveDate::from_isoywd_opt(year as i32, week as u32, chrono::Weekday::Mon)
+}
+
+/// カードにIDと日時を付けます。いつ作られたかは分からないので、その日の0時にします。
+fn add_card_ids(table: &mut toml::value::Table) -> Result<(), String> {
+    let date = bucket_date(table).ok_or_else(|| i18n::tr("日付を読めません。").to_string())?;
+    let timestamp = chrono::Local
+        .from_local_datetime(&date.and_hms(0, 0, 0))
+        .earliest()
+        .unwrap_or_else(chrono::Local::now)
+        .to_rfc3339();
+    let cards = match table.get_mut("cards") {
+        Some(toml::Value::Array(cards)) => cards,
+        Some(_) => return Err(i18n::tr("カードの一覧を読めません。").to_string()),
+        None => return Ok(()),
+    };
+    for card in cards {
+        let card = card
+            .as_table_mut()
+            .ok_or_else(|| i18n::tr("カードの一覧を読めません。").to_string())?;
+        // この頃のファイルは、既定のキーしか使っていません。
+        let done = card
+            .get("key")
+            .and_then(|key| key.as_str())
+            .and_then(|key| CardKeys::default().task(key))
+            == Some(Task::Done);
+        card.insert(
+            "id".to_string(),
+            toml::Value::String(uuid::Uuid::new_v4().to_string()),
+        );
+        for name in ["created_at", "updated_at"] {
+            card.insert(name.to_string(), toml::Value::String(timestamp.clone()));
+        }
+        if done {
+            card.insert(
+                "completed_at".to_string(),
+                toml::Value::String(timestamp.clone()),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// 保存するファイルの形式のバージョンです。
+pub fn current_version() -> usize {
+    MIGRATIONS.len() + 1
+}
+
+/// `root/YYYY/YYYY-Www.toml`です。年は週の年です。
+pub fn weekly_bucket_file_path(root: &str, week: chrono::IsoWeek) -> String {
+    weekly_file_path(root, week.year(), week.week())
+}
+
+fn weekly_file_path(root: &str, year: i32, week: u32) -> String {
+    crate::libs::expand_path(&format!(
+        "{}/{:04}/{:04}-W{:02}.toml",
+        root, year, year, week
+    ))
+}
+
+/// ファイルの内容を読み、古い形式なら今の形式に変換します。変換したかどうかも返します。
+fn parse_daily_bucket(path: &str, content: &str) -> Result<(DailyBucket, bool), StorageError> {
+    parse_bucket(path, content)
+}
+
+fn parse_bucket<T: serde::de::DeserializeOwned>(
+    path: &str,
+    content: &str,
+) -> Result<(T, bool), StorageError> {
+    let parse_error = |error| StorageError::Parse {
+        path: path.to_string(),
+        error,
+    };
+    let invalid = |key: &str| StorageError::Invalid {
+        path: path.to_string(),
+        key: key.to_string(),
+    };
+    let mut table = match content.parse::<toml::Value>().map_err(parse_error)? {
+        toml::Value::Table(table) => table,
+        _ => return Err(invalid("")),
+    };
+    // バージョンを書いていなかった頃のファイルは、バージョン1として扱います。
+    let version = match table.get("version") {
+        None => 1,
+        Some(toml::Value::Integer(version)) if *version >= 1 => *version as usize,
+        Some(_) => return Err(invalid("version")),
+    };
+    let has_version = table.contains_key("version");
+    table.insert("version".to_string(), toml::Value::Integer(version as i64));
+    if version > current_version() {
+        return Err(StorageError::UnsupportedVersion {
+            path: path.to_string(),
+            version,
+        });
+    }
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version - 1) {
+        migration(&mut table).map_err(|message| StorageError::Migration {
+            path: path.to_string(),
+            version: i + 1,
+            message,
+        })?;
+        table.insert("version".to_string(), toml::Value::Integer(i as i64 + 2));
+    }
+    let bucket = toml::Value::Table(table)
+        .try_into::<T>()
+        .map_err(parse_error)?;
+
+    Ok((bucket, !has_version || version < current_version()))
+}
+
+/// `date`のカードを読み込みます。ファイルが無ければ空の`DailyBucket`を返します。
+pub fn load_daily_bucket(root: &str, date: chrono::NaiveDate) -> Result<DailyBucket, StorageError> {
+    let path = bucket_file_path(root, date);
+    match fs::read_to_string(&path) {
+        Ok(content) => parse_daily_bucket(&path, &content).map(|(daily_bucket, _)| daily_bucket),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(DailyBucket::new(date, vec![])),
+        Err(error) => Err(StorageError::Io { path, error }),
+    }
+}
+
+/// `week`のメモを読み込みます。ファイルが無ければ空の`WeeklyBucket`を返します。
+pub fn load_weekly_bucket(root: &str, week: chrono::IsoWeek) -> Result<WeeklyBucket, StorageError> {
+    let path = weekly_bucket_file_path(root, week);
+    match fs::read_to_string(&path) {
+        Ok(content) => parse_bucket(&path, &content).map(|(weekly_bucket, _)| weekly_bucket),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            Ok(WeeklyBucket::new(week, vec![]))
+        }
+        Err(error) => Err(StorageError::Io { path, error }),
+    }
+}
+
+pub fn save_weekly_bucket(
+    root: &str,
+    weekly_bucket: &WeeklyBucket,
+    backups: usize,
+) -> Result<(), StorageError> {
+    let dest = path::PathBuf::from(weekly_file_path(
+        root,
+        weekly_bucket.year,
+        weekly_bucket.week,
+    ));
+    let io_error = |error| StorageError::Io {
+        path: dest.display().to_string(),
+        error,
+    };
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let content = toml::to_string_pretty(weekly_bucket).map_err(StorageError::Serialize)?;
+    crate::libs::write_atomically(&dest, content.as_bytes(), backups).map_err(io_error)
+}
+
+fn migrate_bucket_file<T: serde::Serialize + serde::de::DeserializeOwned>(
+    path: &path::Path,
+    backups: usize,
+) -> Result<bool, StorageError> {
+    let path_string = path.display().to_string();
+    let io_error = |error| StorageError::Io {
+        path: path_string.clone(),
+        error,
+    };
+    let content = fs::read_to_string(path).map_err(io_error)?;
+    let (bucket, migrated) = parse_bucket::<T>(&path_string, &content)?;
+    if migrated {
+        let content = toml::to_string_pretty(&bucket).map_err(StorageError::Serialize)?;
+        crate::libs::write_atomically(path, content.as_bytes(), backups).map_err(io_error)?;
+    }
+
+    Ok(migrated)
+}
+
+/// 古い形式のファイルを今の形式で保存し直します。保存し直したかどうかを返します。
+pub fn migrate_daily_bucket_file(path: &path::Path, backups: usize) -> Result<bool, StorageError> {
+    migrate_bucket_file::<DailyBucket>(path, backups)
+}
+
+/// 古い形式の週のメモを今の形式で保存し直します。保存し直したかどうかを返します。
+pub fn migrate_weekly_bucket_file(path: &path::Path, backups: usize) -> Result<bool, StorageError> {
+    migrate_bucket_file::<WeeklyBucket>(path, backups)
+}
+
+/// `root`の下にある、`parse`で名前を解釈できるファイルを順に返します。
+fn find_bucket_files<K: Ord>(
+    root: &str,
+    parse: fn(&str) -> Option<K>,
+) -> Result<Vec<(K, path::PathBuf)>, StorageError> {
+    fn walk<K>(
+        dir: &path::Path,
+        parse: fn(&str) -> Option<K>,
+        files: &mut Vec<(K, path::PathBuf)>,
+    ) -> Result<(), StorageError> {
+        let io_error = |error| StorageError::Io {
+            path: dir.display().to_string(),
+            error,
+        };
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.is_dir() {
+                walk(&path, parse, files)?;
+                continue;
+            }
+            // 一時ファイルやバックアップは、名前が.tomlで終わらないので含まれません。
+            let key = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".toml"))
+                .and_then(parse);
+            if let Some(key) = key {
+                files.push((key, path));
+            }
+        }
+        Ok(())
+    }
+
+    let root = crate::libs::expand_path(root);
+    let mut files = vec![];
+    if path::Path::new(&root).exists() {
+        walk(path::Path::new(&root), parse, &mut files)?;
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(files)
+}
+
+/// `root`の下にある、日ごとのカードのファイルを日付の順に返します。
+pub fn find_daily_bucket_files(
+    root: &str,
+) -> Result<Vec<(chrono::NaiveDate, path::PathBuf)>, StorageError> {
+    find_bucket_files(root, |name| {
+        chrono::NaiveDate::parse_from_str(name, "%Y-%m-%d").ok()
+    })
+}
+
+/// `root`の下にある、週のメモのファイルを週の順に返します。
+pub fn find_weekly_bucket_files(
+    root: &str,
+) -> Result<Vec<(chrono::IsoWeek, path::PathBuf)>, StorageError> {
+    find_bucket_files(root, parse_week)
+}
+
+/// `YYYY-Www`を週として解釈します。
+fn parse_week(name: &str) -> Option<chrono::IsoWeek> {
+    let (year, week) = name.split_once("-W")?;
+    if year.len() != 4 || week.len() != 2 {
+        return None;
+    }
+    chrono::NaiveDate::from_isoywd_opt(year.parse().ok()?, week.parse().ok()?, chrono::Weekday::Mon)
+        .map(|date| date.iso_week())
+}
+
+/// `date`の月のうち、カードのファイルがある日を返します。
+pub fn dates_with_cards(root: &str, date: chrono::NaiveDate) -> Vec<chrono::NaiveDate> {
+    let entries = match fs::read_dir(bucket_dir(root, date)) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut dates = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let name = name.to_str()?.strip_suffix(".toml")?;
+            chrono::NaiveDate::parse_from_str(name, "%Y-%m-%d").ok()
+        })
+        .filter(|found| found.year() == date.year() && found.month() == date.month())
+        .collect::<Vec<chrono::NaiveDate>>();
+    dates.sort();
+
+    dates
+}
+
+/// `root`の下のカードを、全文検索できるように読み込んでおきます。
+#[derive(Debug, Default)]
+pub struct CardIndex {
+    files: collections::BTreeMap<path::PathBuf, IndexedFile>,
+}
+
+#[derive(Debug)]
+struct IndexedFile {
+    /// 前に読み込んだときの更新日時です。変わっていなければ読み直しません。
+    modified: Option<std::time::SystemTime>,
+    date: chrono::NaiveDate,
+    cards: Vec<Card>,
+    /// 大文字と小文字を区別せずに探すための、小文字にしたカードの本文です。
+    texts: Vec<String>,
+}
+
+impl CardIndex {
+    /// `root`の下のファイルのうち、前に読み込んでから変更されたものだけを読み直します。読み込めないファイルは除きます。
+    pub fn refresh(&mut self, root: &str) -> Result<(), StorageError> {
+        let files = find_daily_bucket_files(root)?;
+        let paths = files
+            .iter()
+            .map(|(_, path)| path.clone())
+            .collect::<collections::HashSet<path::PathBuf>>();
+        self.files.retain(|path, _| paths.contains(path));
+
+        for (date, path) in files {
+            let modified = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            if let Some(file) = self.files.get(&path) {
+                if modified.is_some() && file.modified == modified {
+                    continue;
+                }
+            }
+            let path_string = path.display().to_string();
+            let result = fs::read_to_string(&path)
+                .map_err(|error| StorageError::Io {
+                    path: path_string.clone(),
+                    error,
+                })
+                .and_then(|content| parse_daily_bucket(&path_string, &content));
+            match result {
+                Ok((daily_bucket, _)) => {
+                    let cards = daily_bucket
+                        .cards
+                        .into_iter()
+                        .filter(|card| !card.text.is_empty())
+                        .collect::<Vec<Card>>();
+                    let texts = cards.iter().map(|card| card.text.to_lowercase()).collect();
+                    self.files.insert(
+                        path,
+                        IndexedFile {
+                            modified,
+                            date,
+                            cards,
+                            texts,
+                        },
+                    );
+                }
+                Err(error) => {
+                    log::warn!("検索の対象から外します: {}", error);
+                    self.files.remove(&path);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `query`を空白で区切ったすべての語を含むカードを、新しい日から順に返します。
+    /// `key`を指定した場合は、そのキーのカードだけを返します。`query`が空なら、`key`のカードをすべて返します。
+    pub fn search(&self, query: &str, key: Option<&str>) -> Vec<(chrono::NaiveDate, &Card)> {
+        let terms = query
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<String>>();
+        if terms.is_empty() && key.is_none() {
+            return vec![];
+        }
+
+        let mut found = vec![];
+        for file in self.files.values().rev() {
+            for (card, text) in file.cards.iter().zip(&file.texts) {
+                let key_matches = key.map_or(true, |key| card.key == key);
+                if key_matches && terms.iter().all(|term| text.contains(term.as_str())) {
+                    found.push((file.date, card));
+                }
+            }
+        }
+        found.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+
+        found
+    }
+
+    /// IDが`id`のカードと、その日を返します。
+    pub fn find(&self, id: uuid::Uuid) -> Option<(chrono::NaiveDate, &Card)> {
+        self.files.values().find_map(|file| {
+            file.cards
+                .iter()
+                .find(|card| card.id == id)
+                .map(|card| (file.date, card))
+        })
+    }
+}
+
+/// `backups`は、残しておく以前の内容の世代数です。
+pub fn save_daily_bucket(
+    root: &str,
+    daily_bucket: &DailyBucket,
+    backups: usize,
+) -> Result<(), StorageError> {
+    let dest = bucket_file_path(root, daily_bucket.date);
+    let io_error = |error| StorageError::Io {
+        path: dest.clone(),
+        error,
+    };
+    fs::create_dir_all(bucket_dir(root, daily_bucket.date)).map_err(io_error)?;
+    let content = toml::to_string_pretty(daily_bucket).map_err(StorageError::Serialize)?;
+    log::debug!("保存先: {}, 保存内容:\n{}", &dest, &content);
+    crate::libs::write_atomically(path::Path::new(&dest), content.as_bytes(), backups)
+        .map_err(io_error)
+}
+
+/// `today`より前の日の、本文のある終わっていない作業のカードを`today`に持ち越します。持ち越したカードの数を返します。
+/// `since`があれば、その日から前の日までだけを見ます。無ければ`root`の下のすべての日を見ます。読み込めなかった日は飛ばします。
+pub fn carry_over(
+    root: &str,
+    today: chrono::NaiveDate,
+    since: Option<chrono::NaiveDate>,
+    mode: CarryOverMode,
+    keys: &CardKeys,
+    backups: usize,
+) -> Result<usize, StorageError> {
+    let dates = match since {
+        Some(since) => {
+            let mut dates = vec![];
+            let mut date = since;
+            while date < today {
+                dates.push(date);
+                date = date.succ();
+            }
+            dates
+        }
+        None => find_daily_bucket_files(root)?
+            .into_iter()
+            .map(|(date, _)| date)
+            .filter(|date| *date < today)
+            .collect(),
+    };
+    let mut past = vec![];
+    for date in dates {
+        match load_daily_bucket(root, date) {
+            Ok(daily_bucket) => past.push(daily_bucket),
+            Err(error) => log::warn!("持ち越しの対象から外します: {}", error),
+        }
+    }
+    let mut today_bucket = load_daily_bucket(root, today)?;
+
+    // 写したことのあるカードを、また写さないようにします。
+    let copied = past
+        .iter()
+        .chain(std::iter::once(&today_bucket))
+        .flat_map(|daily_bucket| &daily_bucket.cards)
+        .filter_map(|card| card.carried_from.as_ref())
+        .map(|carried_from| carried_from.id)
+        .collect::<std::collections::HashSet<uuid::Uuid>>();
+    let pending = |card: &Card| {
+        keys.task(&card.key) == Some(Task::Open)
+            && !card.text.is_empty()
+            && (mode == CarryOverMode::Move || !copied.contains(&card.id))
+    };
+
+    let now = chrono::Local::now();
+    let mut changed = vec![];
+    let mut count = 0;
+    for daily_bucket in &mut past {
+        let (carried, rest): (Vec<Card>, Vec<Card>) = daily_bucket
+            .cards
+            .iter()
+            .cloned()
+            .partition(|card| pending(card));
+        if carried.is_empty() {
+            continue;
+        }
+        count += carried.len();
+        for card in carried {
+            let carried_from = CarriedFrom {
+                date: card
+                    .carried_from
+                    .as_ref()
+                    .map(|carried_from| carried_from.date)
+                    .unwrap_or(daily_bucket.date),
+                id: card.id,
+            };
+            let mut card = match mode {
+                CarryOverMode::Move => card,
+                CarryOverMode::Copy => Card::new(card.key, card.text, keys),
+            };
+            card.carried_from = Some(carried_from);
+            card.updated_at = now;
+            today_bucket.cards.push(card);
+        }
+        if mode == CarryOverMode::Move {
+            daily_bucket.cards = rest;
+            changed.push(daily_bucket);
+        }
+    }
+    if count == 0 {
+        return Ok(0);
+    }
+
+    // 途中で失敗してもカードを失わないように、持ち越し先から保存します。
+    save_daily_bucket(root, &today_bucket, backups)?;
+    for daily_bucket in changed {
+        save_daily_bucket(root, daily_bucket, backups)?;
+    }
+
+    Ok(count)
+}
+
+/// `date`を含む、`week_start`の曜日から始まる週の最初の日を返します。
+pub fn compute_week_start(
+    date: chrono::NaiveDate,
+    week_start: chrono::Weekday,
+) -> chrono::NaiveDate {
+    let days = (date.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7;
+    date - chrono::Duration::days(days as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn card(key: &str, text: &str) -> Card {
+        Card::new(key.to_string(), text.to_string(), &CardKeys::default())
+    }
+
+    fn test_root(name: &str) -> String {
+        crate::libs::test_dir(name).display().to_string()
+    }
+
+    fn save(root: &str, date: NaiveDate, cards: Vec<Card>) {
+        save_daily_bucket(root, &DailyBucket::new(date, cards), 0).unwrap();
+    }
+
+    fn texts(root: &str, date: NaiveDate) -> Vec<String> {
+        load_daily_bucket(root, date)
+            .unwrap()
+            .cards
+            .into_iter()
+            .map(|card| card.text)
+            .collect()
+    }
+
+    #[test]
+    fn load_daily_bucket_without_file_is_empty() {
+        let root = test_root("load-missing");
+
+        assert!(load_daily_bucket(&root, date("2026-10-18"))
+            .unwrap()
+            .cards
+            .is_empty());
+    }
+
+    #[test]
+    fn save_and_load_daily_bucket() {
+        let root = test_root("save-and-load");
+        let today = date("2026-10-18");
+        save(&root, today, vec![card("📝", "a"), card("🔲", "b")]);
+
+        assert_eq!(texts(&root, today), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn load_daily_bucket_reports_broken_file() {
+        let root = test_root("load-broken");
+        let today = date("2026-10-18");
+        save(&root, today, vec![]);
+        fs::write(bucket_file_path(&root, today), "cards = [").unwrap();
+
+        assert!(matches!(
+            load_daily_bucket(&root, today),
+            Err(StorageError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn load_daily_bucket_reports_card_without_text() {
+        let root = test_root("load-without-text");
+        let today = date("2026-10-18");
+        save(&root, today, vec![]);
+        fs::write(
+            bucket_file_path(&root, today),
+            "date = \"2026-10-18\"\ncards = [{ key = \"📝\" }]",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            load_daily_bucket(&root, today),
+            Err(StorageError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn current_version_follows_migrations() {
+        assert_eq!(current_version(), MIGRATIONS.len() + 1);
+    }
+
+    #[test]
+    fn parse_daily_bucket_migrates_version_1() {
+        let content = r#"
+date = "2026-10-18"
+
+[[cards]]
+key = "🔲"
+text = "a"
+
+[[cards]]
+key = "✅"
+text = "b"
+"#;
+        let (daily_bucket, migrated) = parse_daily_bucket("a.toml", content).unwrap();
+
+        assert!(migrated);
+        assert_eq!(daily_bucket.version, current_version());
+        assert_eq!(daily_bucket.date, date("2026-10-18"));
+        assert_eq!(daily_bucket.cards.len(), 2);
+        assert_ne!(daily_bucket.cards[0].id, daily_bucket.cards[1].id);
+        assert_eq!(
+            daily_bucket.cards[0].created_at.naive_local().date(),
+            date("2026-10-18")
+        );
+        assert_eq!(daily_bucket.cards[0].completed_at, None);
+        assert!(daily_bucket.cards[1].completed_at.is_some());
+    }
+
+    #[test]
+    fn parse_daily_bucket_keeps_current_version() {
+        let content =
+            toml::to_string(&DailyBucket::new(date("2026-10-18"), vec![card("📝", "a")])).unwrap();
+        let (daily_bucket, migrated) = parse_daily_bucket("a.toml", &content).unwrap();
+
+        assert!(!migrated);
+        assert_eq!(daily_bucket.cards[0].text, "a");
+    }
+
+    #[test]
+    fn parse_daily_bucket_rejects_newer_version() {
+        let content = format!(
+            "version = {}\ndate = \"2026-10-18\"\ncards = []\n",
+            current_version() + 1
+        );
+
+        assert!(matches!(
+            parse_daily_bucket("a.toml", &content),
+            Err(StorageError::UnsupportedVersion { version, .. }) if version == current_version() + 1
+        ));
+    }
+
+    #[test]
+    fn parse_daily_bucket_rejects_invalid_version() {
+        for version in ["0", "\"1\""] {
+            let content = format!("version = {}\ndate = \"2026-10-18\"\ncards = []\n", version);
+
+            assert!(matches!(
+                parse_daily_bucket("a.toml", &content),
+                Err(StorageError::Invalid { key, .. }) if key == "version"
+            ));
+        }
+    }
+
+    #[test]
+    fn parse_daily_bucket_reports_migration_error() {
+        let content = "date = \"2026-10-18\"\ncards = \"a\"\n";
+
+        assert!(matches!(
+            parse_daily_bucket("a.toml", content),
+            Err(StorageError::Migration { version: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn parse_weekly_bucket_migrates_version_1() {
+        let content = "year = 2026\nweek = 42\n\n[[cards]]\nkey = \"📝\"\ntext = \"a\"\n";
+        let (weekly_bucket, migrated) = parse_bucket::<WeeklyBucket>("a.toml", content).unwrap();
+
+        assert!(migrated);
+        assert_eq!(weekly_bucket.version, current_version());
+        assert_eq!(
+            weekly_bucket.cards[0].created_at.naive_local().date(),
+            date("2026-10-12")
+        );
+    }
+
+    #[test]
+    fn same_cards_ignores_empty_cards() {
+        let a = card("📝", "a");
+        let b = card("📝", "b");
+        let empty = card("📝", "");
+
+        assert!(same_cards(
+            &[a.clone(), empty, b.clone()],
+            &[a.clone(), b.clone()]
+        ));
+        assert!(!same_cards(&[a.clone(), b.clone()], &[b, a.clone()]));
+        assert!(!same_cards(&[a], &[]));
+    }
+
+    #[test]
+    fn merge_cards_prefers_newer_cards() {
+        let keys = CardKeys::default();
+        let a = card("📝", "a");
+        let b = card("📝", "b");
+        let mut newer_a = a.clone();
+        newer_a.update("📝", "a2", &keys);
+        let mut newer_b = b.clone();
+        newer_b.update("📝", "b2", &keys);
+        let mine_only = card("📝", "c");
+
+        let merged = merge_cards(&[a, newer_b], &[newer_a, b, mine_only, card("📝", "")]);
+
+        assert_eq!(
+            merged
+                .iter()
+                .map(|card| card.text.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["a2", "b2", "c"]
+        );
+    }
+
+    #[test]
+    fn carry_over_moves_open_cards() {
+        let root = test_root("carry-over-move");
+        let yesterday = date("2026-10-17");
+        let today = date("2026-10-18");
+        let open = card("🔲", "a");
+        save(
+            &root,
+            yesterday,
+            vec![open.clone(), card("✅", "b"), card("🔲", "")],
+        );
+        save(&root, today, vec![card("📝", "c")]);
+
+        let count = carry_over(
+            &root,
+            today,
+            None,
+            CarryOverMode::Move,
+            &CardKeys::default(),
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(texts(&root, yesterday), vec!["b", ""]);
+        let today_bucket = load_daily_bucket(&root, today).unwrap();
+        assert_eq!(today_bucket.cards[1].id, open.id);
+        assert_eq!(
+            today_bucket.cards[1].carried_from,
+            Some(CarriedFrom {
+                date: yesterday,
+                id: open.id,
+            })
+        );
+    }
+
+    #[test]
+    fn carry_over_copies_open_cards_once() {
+        let root = test_root("carry-over-copy");
+        let yesterday = date("2026-10-17");
+        let today = date("2026-10-18");
+        save(&root, yesterday, vec![card("🔲", "a")]);
+        let keys = CardKeys::default();
+
+        let count = carry_over(&root, today, None, CarryOverMode::Copy, &keys, 0).unwrap();
+        let again = carry_over(&root, today, None, CarryOverMode::Copy, &keys, 0).unwrap();
+
+        assert_eq!((count, again), (1, 0));
+        assert_eq!(texts(&root, yesterday), vec!["a"]);
+        assert_eq!(texts(&root, today), vec!["a"]);
+    }
+
+    #[test]
+    fn carry_over_since_skips_older_days() {
+        let root = test_root("carry-over-since");
+        let today = date("2026-10-18");
+        save(&root, date("2026-10-01"), vec![card("🔲", "old")]);
+        save(&root, date("2026-10-16"), vec![card("🔲", "new")]);
+
+        let count = carry_over(
+            &root,
+            today,
+            Some(date("2026-10-10")),
+            CarryOverMode::Move,
+            &CardKeys::default(),
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(texts(&root, today), vec!["new"]);
+        assert_eq!(texts(&root, date("2026-10-01")), vec!["old"]);
+    }
+
+    #[test]
+    fn search_finds_cards_newest_first() {
+        let root = test_root("search");
+        save(
+            &root,
+            date("2026-10-17"),
+            vec![card("📝", "Foo bar"), card("🔲", "baz")],
+        );
+        save(
+            &root,
+            date("2026-10-18"),
+            vec![card("🔲", "foo"), card("📝", "")],
+        );
+        let mut index = CardIndex::default();
+        index.refresh(&root).unwrap();
+        let found = |query: &str, key: Option<&str>| {
+            index
+                .search(query, key)
+                .into_iter()
+                .map(|(date, card)| (date.format("%d").to_string(), card.text.clone()))
+                .collect::<Vec<(String, String)>>()
+        };
+        let pair = |day: &str, text: &str| (day.to_string(), text.to_string());
+
+        assert_eq!(
+            found("FOO", None),
+            vec![pair("18", "foo"), pair("17", "Foo bar")]
+        );
+        assert_eq!(found("foo bar", None), vec![pair("17", "Foo bar")]);
+        assert_eq!(found("foo", Some("🔲")), vec![pair("18", "foo")]);
+        assert_eq!(
+            found("", Some("🔲")),
+            vec![pair("18", "foo"), pair("17", "baz")]
+        );
+        assert!(found("", None).is_empty());
+    }
+
+    #[test]
+    fn find_returns_card_and_date() {
+        let root = test_root("find");
+        let a = card("📝", "a");
+        save(&root, date("2026-10-17"), vec![card("📝", "b")]);
+        save(&root, date("2026-10-18"), vec![a.clone()]);
+        let mut index = CardIndex::default();
+        index.refresh(&root).unwrap();
+
+        let (found_date, found) = index.find(a.id).unwrap();
+        assert_eq!(found_date, date("2026-10-18"));
+        assert_eq!(found.text, "a");
+        assert!(index.find(uuid::Uuid::new_v4()).is_none());
+    }
+
+    #[test]
+    fn compute_week_start_goes_back_to_week_start() {
+        let sunday = date("2026-10-18");
+
+        assert_eq!(compute_week_start(sunday, Weekday::Mon), date("2026-10-12"));
+        assert_eq!(compute_week_start(sunday, Weekday::Sun), sunday);
+        assert_eq!(compute_week_start(sunday, Weekday::Sat), date("2026-10-17"));
+    }
+}
@@ -0,0 +1,333 @@
+//! `coo card ...`のように、ウィンドウを開かずにカードを操作するコマンドを扱います。
+use coo::card;
+use coo::i18n::{self, tr};
+use coo::views::assorted_card;
+use gtk::glib;
+
+const USAGE: &str = "使い方:
+  coo [--config <ファイル>] card add [--view <ID>] [--date <YYYY-MM-DD>] [--key <キー>] <テキスト>
+  coo [--config <ファイル>] card list [--view <ID>] [--date <YYYY-MM-DD>] [--week]
+  coo [--config <ファイル>] card done [--view <ID>] <カードID>
+  coo [--config <ファイル>] card carry-over [--view <ID>] [--date <YYYY-MM-DD>]
+  coo [--config <ファイル>] migrate [--view <ID>]
+
+カードIDは`card list`が表示するUUIDです。`YYYY-MM-DD#番号`で、その日の何番目のカードかを指定することもできます。";
+
+/// GLibの`G_OPTION_REMAINING`です。オプションでない残りの引数を受け取ります。
+pub const REMAINING: &str = "";
+
+pub struct Invocation {
+    options: Options,
+    args: Vec<String>,
+}
+
+#[derive(Default)]
+struct Options {
+    view: Option<String>,
+    date: Option<String>,
+    key: Option<String>,
+    week: bool,
+    positionals: Vec<String>,
+}
+
+fn lookup_string(options: &glib::VariantDict, name: &str) -> Option<String> {
+    options.lookup_value(name, None)?.get::<String>()
+}
+
+/// `handle_local_options`が受け取ったオプションに、オプションでない引数があれば、コマンドとして`Invocation`を返します。
+/// 無ければ、GUIとして起動します。
+pub fn parse(options: &glib::VariantDict) -> Option<Invocation> {
+    let args = options
+        .lookup_value(REMAINING, None)?
+        .get::<Vec<String>>()
+        .filter(|args| !args.is_empty())?;
+
+    Some(Invocation {
+        options: Options {
+            view: lookup_string(options, "view"),
+            date: lookup_string(options, "date"),
+            key: lookup_string(options, "key"),
+            week: options.contains("week"),
+            positionals: Vec::new(),
+        },
+        args,
+    })
+}
+
+fn parse_date(value: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| i18n::format("日付はYYYY-MM-DDの形式で指定してください: {}", &[&value]))
+}
+
+/// `--date`の日を返します。指定されていなければ今日です。
+fn date_or_today(options: &Options) -> Result<chrono::NaiveDate, String> {
+    options
+        .date
+        .as_deref()
+        .map_or_else(|| Ok(today()), parse_date)
+}
+
+/// `--view`で指定された、または最初のassorted_cardビューの設定を返します。`root`は展開済みです。
+fn find_view(
+    config_file_path: &str,
+    view_id: Option<&str>,
+) -> Result<assorted_card::Config, String> {
+    let config = coo::config::load(config_file_path, &coo::views::ViewRegistry::default())
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<String>>()
+                .join("\n")
+        })?;
+    i18n::select(config.locale);
+
+    let view = config
+        .views
+        .iter()
+        .filter(|view| view.component == "assorted_card")
+        .find(|view| match view_id {
+            Some(id) => view.id == id,
+            None => true,
+        })
+        .ok_or_else(|| match view_id {
+            Some(id) => i18n::format("assorted_cardのビューがありません: {}", &[&id]),
+            None => tr("assorted_cardのビューがありません。").to_string(),
+        })?;
+    let mut config = view
+        .config
+        .clone()
+        .try_into::<assorted_card::Config>()
+        .map_err(|error| error.to_string())?;
+    config.root = coo::libs::expand_path(&config.root);
+
+    Ok(config)
+}
+
+/// `YYYY-MM-DD#番号`のカードIDを、日とその日の何番目のカードかに分けます。
+fn parse_card_position(id: &str) -> Option<(chrono::NaiveDate, usize)> {
+    let (date, number) = id.split_once('#')?;
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    match number.parse::<usize>() {
+        Ok(number) if number > 0 => Some((date, number - 1)),
+        _ => None,
+    }
+}
+
+fn today() -> chrono::NaiveDate {
+    chrono::Local::today().naive_local()
+}
+
+fn add(view: &assorted_card::Config, options: Options) -> Result<(), String> {
+    if options.positionals.is_empty() {
+        return Err(tr("テキストを指定してください。").to_string());
+    }
+    let key = options
+        .key
+        .clone()
+        .unwrap_or_else(|| view.keys.first().symbol.clone());
+    if view.keys.find(&key).is_none() {
+        return Err(i18n::format(
+            "キーは{}のいずれかを指定してください: {}",
+            &[&view.keys.symbols().join(" "), &key],
+        ));
+    }
+
+    let date = date_or_today(&options)?;
+    let mut daily_bucket =
+        card::load_daily_bucket(&view.root, date).map_err(|error| error.to_string())?;
+    let card = card::Card::new(key, options.positionals.join(" "), &view.keys);
+    let id = card.id;
+    daily_bucket.cards.push(card);
+    card::save_daily_bucket(&view.root, &daily_bucket, view.backups)
+        .map_err(|error| error.to_string())?;
+    println!("{}", id);
+
+    Ok(())
+}
+
+fn list(view: &assorted_card::Config, options: Options) -> Result<(), String> {
+    let date = date_or_today(&options)?;
+    let dates = if options.week {
+        view.visible_dates(date)
+    } else {
+        vec![date]
+    };
+
+    for date in dates {
+        let daily_bucket =
+            card::load_daily_bucket(&view.root, date).map_err(|error| error.to_string())?;
+        for card in &daily_bucket.cards {
+            // 画面で入力中の空のカードも保存されているので、表示しません。
+            if card.text.is_empty() {
+                continue;
+            }
+            println!(
+                "{}\t{}\t{}\t{}",
+                card.id,
+                date.format("%Y-%m-%d"),
+                card.key,
+                card.text.replace('\n', "\\n")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn done(view: &assorted_card::Config, options: Options) -> Result<(), String> {
+    let id = match options.positionals.as_slice() {
+        [id] => id,
+        _ => return Err(tr("カードIDをひとつ指定してください。").to_string()),
+    };
+    let invalid = || {
+        i18n::format(
+            "カードIDはUUIDかYYYY-MM-DD#番号の形式で指定してください: {}",
+            &[id],
+        )
+    };
+    let not_found = || i18n::format("カードがありません: {}", &[id]);
+
+    let mut daily_bucket;
+    let card = match uuid::Uuid::parse_str(id) {
+        Ok(uuid) => {
+            let mut index = card::CardIndex::default();
+            index
+                .refresh(&view.root)
+                .map_err(|error| error.to_string())?;
+            let (date, _) = index.find(uuid).ok_or_else(not_found)?;
+            daily_bucket =
+                card::load_daily_bucket(&view.root, date).map_err(|error| error.to_string())?;
+            daily_bucket.cards.iter_mut().find(|card| card.id == uuid)
+        }
+        Err(_) => {
+            let (date, index) = parse_card_position(id).ok_or_else(invalid)?;
+            daily_bucket =
+                card::load_daily_bucket(&view.root, date).map_err(|error| error.to_string())?;
+            daily_bucket.cards.get_mut(index)
+        }
+    }
+    .ok_or_else(not_found)?;
+    let done = view
+        .keys
+        .done()
+        .ok_or_else(|| tr("作業を終えたことを表すキーがありません。").to_string())?;
+    let text = card.text.clone();
+    card.update(&done.symbol, &text, &view.keys);
+    card::save_daily_bucket(&view.root, &daily_bucket, view.backups)
+        .map_err(|error| error.to_string())
+}
+
+/// `--date`の日、無ければ今日より前の終わっていない作業のカードを、その日に持ち越します。
+fn carry_over(view: &assorted_card::Config, options: Options) -> Result<(), String> {
+    let date = date_or_today(&options)?;
+    let count = card::carry_over(
+        &view.root,
+        date,
+        None,
+        view.carry_over.mode,
+        &view.keys,
+        view.backups,
+    )
+    .map_err(|error| error.to_string())?;
+    println!(
+        "{}",
+        i18n::format("{}件のカードを持ち越しました。", &[&count])
+    );
+
+    Ok(())
+}
+
+fn run_card(config_file_path: &str, mut options: Options, args: &[String]) -> Result<(), String> {
+    let (subcommand, rest) = args.split_first().ok_or_else(|| tr(USAGE).to_string())?;
+    options.positionals = rest.to_vec();
+    let view = find_view(config_file_path, options.view.as_deref())?;
+    match subcommand.as_str() {
+        "add" => add(&view, options),
+        "list" => list(&view, options),
+        "done" => done(&view, options),
+        "carry-over" => carry_over(&view, options),
+        _ => Err(i18n::format(
+            "不明なコマンドです: {}\n{}",
+            &[subcommand, &tr(USAGE)],
+        )),
+    }
+}
+
+type Migrate = fn(&std::path::Path, usize) -> Result<bool, card::StorageError>;
+
+/// ルートディレクトリの下にある古い形式の日と週のメモのファイルを、今の形式で保存し直します。
+fn run_migrate(config_file_path: &str, options: Options, args: &[String]) -> Result<(), String> {
+    if !args.is_empty() {
+        return Err(tr(USAGE).to_string());
+    }
+    let view = find_view(config_file_path, options.view.as_deref())?;
+    let daily_files =
+        card::find_daily_bucket_files(&view.root).map_err(|error| error.to_string())?;
+    let weekly_files =
+        card::find_weekly_bucket_files(&view.root).map_err(|error| error.to_string())?;
+    let files = daily_files
+        .into_iter()
+        .map(|(_, path)| (path, card::migrate_daily_bucket_file as Migrate))
+        .chain(
+            weekly_files
+                .into_iter()
+                .map(|(_, path)| (path, card::migrate_weekly_bucket_file as Migrate)),
+        )
+        .collect::<Vec<(std::path::PathBuf, Migrate)>>();
+
+    let mut migrated = 0;
+    let mut failed = 0;
+    for (path, migrate) in &files {
+        match migrate(path, view.backups) {
+            Ok(true) => {
+                println!("{}", path.display());
+                migrated += 1;
+            }
+            Ok(false) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+                failed += 1;
+            }
+        }
+    }
+    println!(
+        "{}",
+        i18n::format(
+            "{}件のファイルのうち、{}件を移行しました。",
+            &[&files.len(), &migrated],
+        )
+    );
+
+    if failed > 0 {
+        return Err(i18n::format(
+            "{}件のファイルを読み込めませんでした。",
+            &[&failed],
+        ));
+    }
+    Ok(())
+}
+
+/// コマンドを実行し、終了コードを返します。`config_file_path`は展開済みです。
+pub fn run(invocation: Invocation, config_file_path: &str) -> i32 {
+    let Invocation { options, args } = invocation;
+    let result = match args.split_first() {
+        Some((command, rest)) if command == "card" => run_card(config_file_path, options, rest),
+        Some((command, rest)) if command == "migrate" => {
+            run_migrate(config_file_path, options, rest)
+        }
+        Some((command, _)) => Err(i18n::format(
+            "不明なコマンドです: {}\n{}",
+            &[command, &tr(USAGE)],
+        )),
+        None => Err(tr(USAGE).to_string()),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(message) => {
+            eprintln!("{}", message);
+            1
+        }
+    }
+}
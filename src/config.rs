@@ -0,0 +1,414 @@
+//! 設定ファイル(~/.config/coo.toml)の読み込みと検証を行います。
+use serde::de::DeserializeOwned;
+use std::collections;
+use std::fmt;
+use std::fs;
+use std::path;
+
+use crate::i18n::{self, tr};
+use crate::keybindings;
+use crate::theme;
+use crate::views;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub font: String,
+    pub views: Vec<ViewConfig>,
+    /// キーボードショートカットの名前ごとの、既定値を置き換えるアクセラレータです。
+    pub keybindings: collections::BTreeMap<String, keybindings::Accels>,
+    /// 表示に使う言語です。無ければ環境変数から選びます。
+    pub locale: Option<i18n::Locale>,
+    pub theme: theme::Theme,
+}
+
+/// `config`はコンポーネントごとの設定で、`ViewRegistry`に登録された`ViewFactory`が解釈します。
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ViewConfig {
+    /// 状態の保存などでビューを識別するために使います。省略した場合はタイトルから作り、
+    /// タイトルに英数字が無ければ`view-<番号>`にします。設定ファイルの`id`は`Loader::view`が読みます。
+    #[serde(skip)]
+    pub id: String,
+    pub title: String,
+    pub component: String,
+    #[serde(default = "empty_table")]
+    pub config: toml::Value,
+}
+
+fn empty_table() -> toml::Value {
+    toml::Value::Table(toml::value::Table::new())
+}
+
+/// 設定ファイルの問題をひとつ表します。`key`はTOML上の位置で、ファイル全体の問題であれば空です。
+#[derive(Debug, Clone)]
+pub struct ConfigError {
+    pub path: path::PathBuf,
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.key.is_empty() {
+            write!(f, "{}: {}", self.path.display(), self.message)
+        } else {
+            write!(f, "{}: {}: {}", self.path.display(), self.key, self.message)
+        }
+    }
+}
+
+struct Loader<'a> {
+    path: &'a path::Path,
+    registry: &'a views::ViewRegistry,
+    view_ids: collections::HashSet<String>,
+    errors: Vec<ConfigError>,
+}
+
+impl<'a> Loader<'a> {
+    fn report(&mut self, key: &str, message: String) {
+        self.errors.push(ConfigError {
+            path: self.path.to_path_buf(),
+            key: key.to_string(),
+            message,
+        });
+    }
+
+    fn parse<T: DeserializeOwned>(&mut self, key: &str, value: toml::Value) -> Option<T> {
+        match value.try_into() {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.report(key, error.to_string());
+                None
+            }
+        }
+    }
+
+    fn required<T: DeserializeOwned>(
+        &mut self,
+        table: &mut toml::value::Table,
+        prefix: &str,
+        key: &str,
+    ) -> Option<T> {
+        let key_path = join_key(prefix, key);
+        match table.remove(key) {
+            Some(value) => self.parse(&key_path, value),
+            None => {
+                self.report(&key_path, tr("必須の項目がありません。").to_string());
+                None
+            }
+        }
+    }
+
+    fn optional<T: DeserializeOwned + Default>(
+        &mut self,
+        table: &mut toml::value::Table,
+        prefix: &str,
+        key: &str,
+    ) -> T {
+        match table.remove(key) {
+            Some(value) => self
+                .parse(&join_key(prefix, key), value)
+                .unwrap_or_default(),
+            None => T::default(),
+        }
+    }
+
+    fn unknown_keys(&mut self, table: toml::value::Table, prefix: &str) {
+        for key in table.keys() {
+            self.report(&join_key(prefix, key), tr("不明な項目です。").to_string());
+        }
+    }
+
+    fn config(&mut self, mut table: toml::value::Table) -> Option<Config> {
+        let font = self.required::<String>(&mut table, "", "font");
+        let views = self
+            .required::<Vec<toml::Value>>(&mut table, "", "views")
+            .map(|views| {
+                views
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, view)| self.view(&format!("views[{}]", i), i, view))
+                    .collect::<Vec<ViewConfig>>()
+            });
+        let keybindings = self.keybindings(&mut table);
+        let locale = self.optional(&mut table, "", "locale");
+        let theme = self.theme(&mut table);
+        self.unknown_keys(table, "");
+
+        Some(Config {
+            font: font?,
+            views: views?,
+            keybindings,
+            locale,
+            theme,
+        })
+    }
+
+    fn theme(&mut self, table: &mut toml::value::Table) -> theme::Theme {
+        let theme: theme::Theme = self.optional(table, "", "theme");
+        for (key, color) in &theme.key_colors {
+            let key_path = join_key("theme.key_colors", key);
+            if color.parse::<gdk::RGBA>().is_err() {
+                self.report(&key_path, i18n::format("色を解釈できません: {}", &[color]));
+            }
+        }
+
+        theme
+    }
+
+    fn keybindings(
+        &mut self,
+        table: &mut toml::value::Table,
+    ) -> collections::BTreeMap<String, keybindings::Accels> {
+        let mut keybindings: collections::BTreeMap<String, keybindings::Accels> =
+            self.optional(table, "", "keybindings");
+        keybindings.retain(|name, _| {
+            let known = keybindings::find(name).is_some();
+            if !known {
+                self.report(
+                    &join_key("keybindings", name),
+                    tr("不明なキーボードショートカットです。").to_string(),
+                );
+            }
+            known
+        });
+        for (name, accel, other) in keybindings::find_conflicts(&keybindings) {
+            self.report(
+                &join_key("keybindings", &name),
+                i18n::format(
+                    "{}は、キーボードショートカット{}と重なっています。",
+                    &[&accel, &other],
+                ),
+            );
+        }
+
+        keybindings
+    }
+
+    fn view(&mut self, key: &str, index: usize, value: toml::Value) -> Option<ViewConfig> {
+        let mut table = self.parse::<toml::value::Table>(key, value)?;
+        let id = self.optional::<Option<String>>(&mut table, key, "id");
+        let mut view = self.parse::<ViewConfig>(key, toml::Value::Table(table))?;
+        view.id = match id {
+            Some(id) if id.is_empty() => {
+                self.report(&join_key(key, "id"), tr("IDが空です。").to_string());
+                return None;
+            }
+            Some(id) => id,
+            None => match crate::libs::slugify(&view.title) {
+                slug if slug.is_empty() => format!("view-{}", index + 1),
+                slug => slug,
+            },
+        };
+        if !self.view_ids.insert(view.id.clone()) {
+            self.report(
+                &join_key(key, "id"),
+                i18n::format("IDが重複しています: {}", &[&view.id]),
+            );
+            return None;
+        }
+        match self.registry.validate(&view.component, &view.config) {
+            Ok(()) => Some(view),
+            Err(error @ views::BuildError::UnknownComponent(_)) => {
+                self.report(&join_key(key, "component"), error.to_string());
+                None
+            }
+            Err(error @ views::BuildError::InvalidConfig(_)) => {
+                self.report(&join_key(key, "config"), error.to_string());
+                None
+            }
+        }
+    }
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// 設定ファイルを読み込み、検証します。問題があれば見つかったものをすべて返します。
+/// 各ビューの設定は`registry`に登録されたコンポーネントとして検証します。
+pub fn load<P: AsRef<path::Path>>(
+    path: P,
+    registry: &views::ViewRegistry,
+) -> Result<Config, Vec<ConfigError>> {
+    let mut loader = Loader {
+        path: path.as_ref(),
+        registry,
+        view_ids: collections::HashSet::new(),
+        errors: vec![],
+    };
+
+    let content = match fs::read_to_string(loader.path) {
+        Ok(content) => content,
+        Err(error) => {
+            loader.report("", i18n::format("読み込めません。{}", &[&error]));
+            return Err(loader.errors);
+        }
+    };
+    let table = match content.parse::<toml::Value>() {
+        Ok(toml::Value::Table(table)) => table,
+        Ok(_) => {
+            loader.report("", tr("テーブルではありません。").to_string());
+            return Err(loader.errors);
+        }
+        Err(error) => {
+            loader.report("", error.to_string());
+            return Err(loader.errors);
+        }
+    };
+
+    match loader.config(table) {
+        Some(config) if loader.errors.is_empty() => Ok(config),
+        _ => Err(loader.errors),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_content(name: &str, content: &str) -> Result<Config, Vec<ConfigError>> {
+        let path = crate::libs::test_dir(name).join("coo.toml");
+        fs::write(&path, content).unwrap();
+        load(&path, &views::ViewRegistry::default())
+    }
+
+    fn error_keys(result: Result<Config, Vec<ConfigError>>) -> Vec<String> {
+        result
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.key)
+            .collect()
+    }
+
+    const VIEW: &str = r#"
+[[views]]
+title = "My Cards"
+component = "assorted_card"
+config = { root = "/tmp" }
+"#;
+
+    #[test]
+    fn load_valid_config() {
+        let config =
+            load_content("config-valid", &format!("font = \"Sans 12\"\n{}", VIEW)).unwrap();
+
+        assert_eq!(config.font, "Sans 12");
+        assert_eq!(config.views.len(), 1);
+        assert_eq!(config.views[0].id, "my-cards");
+    }
+
+    #[test]
+    fn load_reports_unreadable_file() {
+        let path = crate::libs::test_dir("config-missing").join("coo.toml");
+
+        assert_eq!(
+            error_keys(load(&path, &views::ViewRegistry::default())),
+            vec![""]
+        );
+    }
+
+    #[test]
+    fn load_reports_syntax_error() {
+        assert_eq!(
+            error_keys(load_content("config-syntax", "font = ")),
+            vec![""]
+        );
+    }
+
+    #[test]
+    fn load_reports_all_errors() {
+        let content = r#"
+unknown = 1
+
+[keybindings]
+unknown-shortcut = ["<Primary>x"]
+"#;
+
+        assert_eq!(
+            error_keys(load_content("config-errors", content)),
+            vec!["font", "views", "keybindings.unknown-shortcut", "unknown"]
+        );
+    }
+
+    #[test]
+    fn load_reports_conflicting_keybindings() {
+        let content = format!(
+            "font = \"Sans 12\"\n{}\n[keybindings]\nundo = \"<Primary>n\"\nredo = \"<Primary>y\"\n",
+            VIEW
+        );
+
+        assert_eq!(
+            error_keys(load_content("config-keybindings", &content)),
+            vec!["keybindings.undo"]
+        );
+    }
+
+    #[test]
+    fn load_reports_invalid_views() {
+        let content = r#"
+font = "Sans 12"
+
+[[views]]
+title = "a"
+component = "unknown"
+
+[[views]]
+title = "b"
+component = "assorted_card"
+
+[[views]]
+title = "c"
+component = "assorted_card"
+config = { root = "/tmp" }
+unknown = 1
+"#;
+
+        assert_eq!(
+            error_keys(load_content("config-views", content)),
+            vec!["views[0].component", "views[1].config", "views[2]"]
+        );
+    }
+
+    #[test]
+    fn load_makes_view_ids() {
+        let content = format!(
+            "font = \"Sans 12\"\n{}{}",
+            VIEW,
+            VIEW.replace("My Cards", "メモ!")
+                .replace("[[views]]", "[[views]]\nid = \"memo\""),
+        );
+        let config = load_content("config-ids", &content).unwrap();
+
+        assert_eq!(
+            config
+                .views
+                .iter()
+                .map(|view| view.id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["my-cards", "memo"]
+        );
+
+        let content = format!("font = \"Sans 12\"\n{}", VIEW.replace("My Cards", "!!!"));
+        let config = load_content("config-ids-fallback", &content).unwrap();
+        assert_eq!(config.views[0].id, "view-1");
+    }
+
+    #[test]
+    fn load_reports_empty_and_duplicate_ids() {
+        let content = format!(
+            "font = \"Sans 12\"\n{}{}",
+            VIEW.replace("[[views]]", "[[views]]\nid = \"\""),
+            VIEW.replace("[[views]]", "[[views]]\nid = \"a\"").repeat(2),
+        );
+
+        assert_eq!(
+            error_keys(load_content("config-duplicate-ids", &content)),
+            vec!["views[0].id", "views[2].id"]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use gtk::prelude::*;
+use std::fs;
+use std::io::prelude::*;
+use std::path;
+
+use crate::Coo;
+use coo::i18n::{self, tr};
+
+const STARTER_CONFIG: &str = include_str!("resources/coo.toml");
+
+/// 初期設定ファイルを作成します。既にファイルがある場合は上書きしません。
+fn create_starter_config(path: &str) -> std::io::Result<()> {
+    if let Some(dir) = path::Path::new(path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(STARTER_CONFIG.as_bytes()))
+}
+
+fn build_error_list(errors: &[coo::config::ConfigError]) -> gtk::Box {
+    let vbox = gtk::BoxBuilder::new()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(4)
+        .build();
+    for error in errors {
+        vbox.add(
+            &gtk::LabelBuilder::new()
+                .label(&error.to_string())
+                .selectable(true)
+                .wrap(true)
+                .xalign(0.0)
+                .build(),
+        );
+    }
+
+    vbox
+}
+
+fn build_editor(config_file_path: &str) -> gtk::Widget {
+    let dir = path::Path::new(config_file_path)
+        .parent()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_else(|| ".".to_string());
+    let editor = coo::views::files_and_file::View::new(&dir);
+    editor.open_file(config_file_path);
+
+    editor.upcast()
+}
+
+/// 設定ファイルを読み込めなかったときに、起動画面の代わりに表示します。
+pub fn show(application: &Coo, config_file_path: &str, errors: &[coo::config::ConfigError]) {
+    let header_bar = gtk::HeaderBarBuilder::new()
+        .title(crate::APPLICATION_NAME)
+        .subtitle(tr("設定ファイルを読み込めませんでした。"))
+        .show_close_button(true)
+        .build();
+
+    let window = gtk::ApplicationWindowBuilder::new()
+        .application(application)
+        .title(crate::APPLICATION_NAME)
+        .window_position(gtk::WindowPosition::Center)
+        .default_width(960)
+        .default_height(640)
+        .build();
+    window.set_titlebar(Some(&header_bar));
+
+    let root = gtk::BoxBuilder::new()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(8)
+        .margin(8)
+        .build();
+
+    let scrolled_window = gtk::ScrolledWindowBuilder::new()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_height(120)
+        .build();
+    scrolled_window.add(&build_error_list(errors));
+    root.add(&scrolled_window);
+
+    let default_config_file_path = coo::libs::expand_path(crate::DEFAULT_CONFIG_FILE_PATH);
+    let buttons = gtk::BoxBuilder::new()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(4)
+        .build();
+
+    let open_button = gtk::Button::with_label(tr("設定ファイルを開く"));
+    open_button.set_sensitive(path::Path::new(config_file_path).is_file());
+    {
+        let root = root.clone();
+        let config_file_path = config_file_path.to_string();
+        open_button.connect_clicked(move |button| {
+            let editor = build_editor(&config_file_path);
+            root.pack_start(&editor, true, true, 0);
+            root.show_all();
+            button.set_sensitive(false);
+        });
+    }
+    buttons.add(&open_button);
+
+    let create_button = gtk::Button::with_label(&i18n::format(
+        "初期設定ファイルを作成 ({})",
+        &[&crate::DEFAULT_CONFIG_FILE_PATH],
+    ));
+    create_button.set_sensitive(!path::Path::new(&default_config_file_path).exists());
+    create_button.connect_clicked(glib::clone!(@weak application, @weak window => move |_| {
+        match create_starter_config(&default_config_file_path) {
+            Ok(()) => {
+                application.set_config_file_path(&default_config_file_path);
+                crate::bootstrap(&application);
+                window.close();
+            }
+            Err(error) => {
+                log::error!("初期設定ファイルを作成できませんでした: {}", error);
+                let dialog = gtk::MessageDialog::new(
+                    Some(&window),
+                    gtk::DialogFlags::MODAL,
+                    gtk::MessageType::Error,
+                    gtk::ButtonsType::Close,
+                    &i18n::format("初期設定ファイルを作成できませんでした。\n{}", &[&error]),
+                );
+                dialog.run();
+                dialog.close();
+            }
+        }
+    }));
+    buttons.add(&create_button);
+
+    let retry_button = gtk::Button::with_label(tr("再試行"));
+    retry_button.connect_clicked(glib::clone!(@weak application, @weak window => move |_| {
+        // 先にウィンドウを閉じると、ウィンドウが無くなった時点でアプリケーションが終了してしまいます。
+        crate::bootstrap(&application);
+        window.close();
+    }));
+    buttons.pack_end(&retry_button, false, false, 0);
+
+    root.add(&buttons);
+    window.add(&root);
+    window.show_all();
+}
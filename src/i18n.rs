@@ -0,0 +1,191 @@
+//! 画面やコマンドに表示する文言の翻訳です。
+//! 文言はgettextのように日本語の原文をそのままIDとして使い、訳は`resources/messages.toml`に置きます。
+use std::collections;
+use std::env;
+use std::fmt;
+use std::sync::atomic;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    Ja,
+    En,
+}
+
+impl Locale {
+    pub const ALL: &'static [Locale] = &[Locale::Ja, Locale::En];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::Ja => "ja",
+            Locale::En => "en",
+        }
+    }
+
+    /// `ja_JP.UTF-8`のようなロケール名から言語を選びます。
+    pub fn parse(name: &str) -> Option<Self> {
+        let language = name.split(['_', '.', '@']).next()?;
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|locale| locale.code() == language)
+    }
+
+    /// `LC_ALL`、`LC_MESSAGES`、`LANG`の順に見て言語を選びます。どれも無ければ日本語、知らない言語なら英語にします。
+    pub fn from_env() -> Self {
+        let name = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty());
+        match name {
+            Some(name) => Self::parse(&name).unwrap_or(Locale::En),
+            None => Locale::Ja,
+        }
+    }
+}
+
+/// 設定ファイルで指定された言語、無ければ環境変数から選んだ言語を使います。
+pub fn select(locale: Option<Locale>) {
+    set_locale(locale.unwrap_or_else(Locale::from_env));
+}
+
+static LOCALE: atomic::AtomicUsize = atomic::AtomicUsize::new(usize::MAX);
+
+pub fn locale() -> Locale {
+    match Locale::ALL.get(LOCALE.load(atomic::Ordering::Relaxed)) {
+        Some(locale) => *locale,
+        None => {
+            let locale = Locale::from_env();
+            set_locale(locale);
+            locale
+        }
+    }
+}
+
+pub fn set_locale(locale: Locale) {
+    let index = Locale::ALL.iter().position(|l| *l == locale).unwrap();
+    LOCALE.store(index, atomic::Ordering::Relaxed);
+}
+
+/// 原文から、言語ごとの訳への対応です。
+type Catalog = collections::HashMap<String, collections::HashMap<String, String>>;
+
+static CATALOG: once_cell::sync::Lazy<Catalog> = once_cell::sync::Lazy::new(|| {
+    #[derive(serde::Deserialize)]
+    struct Messages {
+        messages: Vec<collections::HashMap<String, String>>,
+    }
+
+    let messages: Messages = toml::from_str(include_str!("resources/messages.toml"))
+        .expect("翻訳ファイルを解釈できません。");
+    messages
+        .messages
+        .into_iter()
+        .filter_map(|message| Some((message.get("ja")?.clone(), message)))
+        .collect()
+});
+
+/// `message`を現在の言語に訳します。訳が無ければ原文を返します。
+pub fn tr(message: &'static str) -> &'static str {
+    let code = locale().code();
+    match CATALOG.get(message).and_then(|message| message.get(code)) {
+        Some(translated) => translated,
+        None => {
+            if locale() != Locale::Ja {
+                log::debug!("訳がありません: {}: {}", code, message);
+            }
+            message
+        }
+    }
+}
+
+/// `message`を訳して引数を埋め込みます。`{}`は順番に、`{0}`のように番号を書いた場合はその位置の引数を埋め込みます。
+pub fn format(message: &'static str, args: &[&dyn fmt::Display]) -> String {
+    let mut result = String::new();
+    let mut rest = tr(message);
+    let mut next = 0;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => {
+                result.push_str(&rest[start..]);
+                return result;
+            }
+        };
+        let index = match rest[start + 1..end].parse::<usize>() {
+            Ok(index) => index,
+            Err(_) => {
+                next += 1;
+                next - 1
+            }
+        };
+        if let Some(arg) = args.get(index) {
+            result.push_str(&arg.to_string());
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+
+    result
+}
+
+pub fn weekday_name(weekday: chrono::Weekday) -> &'static str {
+    match locale() {
+        Locale::Ja => match weekday {
+            chrono::Weekday::Mon => "月",
+            chrono::Weekday::Tue => "火",
+            chrono::Weekday::Wed => "水",
+            chrono::Weekday::Thu => "木",
+            chrono::Weekday::Fri => "金",
+            chrono::Weekday::Sat => "土",
+            chrono::Weekday::Sun => "日",
+        },
+        Locale::En => match weekday {
+            chrono::Weekday::Mon => "Mon",
+            chrono::Weekday::Tue => "Tue",
+            chrono::Weekday::Wed => "Wed",
+            chrono::Weekday::Thu => "Thu",
+            chrono::Weekday::Fri => "Fri",
+            chrono::Weekday::Sat => "Sat",
+            chrono::Weekday::Sun => "Sun",
+        },
+    }
+}
+
+/// 年を省いた月日を返します。
+pub fn format_month_day(date: chrono::NaiveDate) -> String {
+    date.format(tr("%-m月%-d日")).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_fills_arguments_in_order() {
+        assert_eq!(format("{}と{}のテスト", &[&1, &"b"]), "1とbのテスト");
+    }
+
+    #[test]
+    fn format_fills_numbered_arguments() {
+        assert_eq!(format("{1}より{0}のテスト", &[&1, &2]), "2より1のテスト");
+    }
+
+    #[test]
+    fn format_leaves_missing_arguments_empty() {
+        assert_eq!(format("[{}][{}]のテスト", &[&1]), "[1][]のテスト");
+    }
+
+    #[test]
+    fn format_keeps_unclosed_brace() {
+        assert_eq!(format("{}と{のテスト", &[&1]), "1と{のテスト");
+    }
+
+    #[test]
+    fn parse_locale_name() {
+        assert_eq!(Locale::parse("ja_JP.UTF-8"), Some(Locale::Ja));
+        assert_eq!(Locale::parse("en"), Some(Locale::En));
+        assert_eq!(Locale::parse("C"), None);
+    }
+}
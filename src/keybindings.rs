@@ -0,0 +1,236 @@
+//! キーボードショートカットの名前と、それが呼び出すアクションと既定のアクセラレータの対応表です。
+//! 設定ファイルの`[keybindings]`で、名前ごとにアクセラレータを置き換えられます。
+use std::collections;
+
+pub struct Keybinding {
+    /// 設定ファイルで使う名前です。
+    pub name: &'static str,
+    /// `gtk::Application::set_accels_for_action`に渡すアクション名です。
+    pub action: &'static str,
+    pub accels: &'static [&'static str],
+}
+
+pub static KEYBINDINGS: &[Keybinding] = &[
+    Keybinding {
+        name: "next-view",
+        action: "win.next-view",
+        accels: &["<Primary>Page_Down"],
+    },
+    Keybinding {
+        name: "previous-view",
+        action: "win.previous-view",
+        accels: &["<Primary>Page_Up"],
+    },
+    Keybinding {
+        name: "view-1",
+        action: "win.select-view(0)",
+        accels: &["<Alt>1"],
+    },
+    Keybinding {
+        name: "view-2",
+        action: "win.select-view(1)",
+        accels: &["<Alt>2"],
+    },
+    Keybinding {
+        name: "view-3",
+        action: "win.select-view(2)",
+        accels: &["<Alt>3"],
+    },
+    Keybinding {
+        name: "view-4",
+        action: "win.select-view(3)",
+        accels: &["<Alt>4"],
+    },
+    Keybinding {
+        name: "view-5",
+        action: "win.select-view(4)",
+        accels: &["<Alt>5"],
+    },
+    Keybinding {
+        name: "view-6",
+        action: "win.select-view(5)",
+        accels: &["<Alt>6"],
+    },
+    Keybinding {
+        name: "view-7",
+        action: "win.select-view(6)",
+        accels: &["<Alt>7"],
+    },
+    Keybinding {
+        name: "view-8",
+        action: "win.select-view(7)",
+        accels: &["<Alt>8"],
+    },
+    Keybinding {
+        name: "view-9",
+        action: "win.select-view(8)",
+        accels: &["<Alt>9"],
+    },
+    Keybinding {
+        name: "previous-week",
+        action: "assorted-card.previous-week",
+        accels: &["<Alt>Left"],
+    },
+    Keybinding {
+        name: "next-week",
+        action: "assorted-card.next-week",
+        accels: &["<Alt>Right"],
+    },
+    Keybinding {
+        name: "this-week",
+        action: "assorted-card.this-week",
+        accels: &["<Alt>Home"],
+    },
+    Keybinding {
+        name: "new-card",
+        action: "assorted-card.new-card",
+        accels: &["<Primary>n"],
+    },
+    Keybinding {
+        name: "next-column",
+        action: "assorted-card.next-column",
+        accels: &["<Primary>bracketright"],
+    },
+    Keybinding {
+        name: "previous-column",
+        action: "assorted-card.previous-column",
+        accels: &["<Primary>bracketleft"],
+    },
+    Keybinding {
+        name: "carry-over",
+        action: "assorted-card.carry-over",
+        accels: &["<Primary><Shift>t"],
+    },
+    Keybinding {
+        name: "undo",
+        action: "assorted-card.undo",
+        accels: &["<Primary>z"],
+    },
+    Keybinding {
+        name: "redo",
+        action: "assorted-card.redo",
+        accels: &["<Primary><Shift>z"],
+    },
+    Keybinding {
+        name: "search-card",
+        action: "assorted-card.search",
+        accels: &["<Primary>f"],
+    },
+    Keybinding {
+        name: "save-file",
+        action: "files-and-file.save",
+        accels: &["<Primary>s"],
+    },
+    Keybinding {
+        name: "parent-directory",
+        action: "files-and-file.go-parent",
+        accels: &["<Alt>Up"],
+    },
+    Keybinding {
+        name: "search-file",
+        action: "files-and-file.search",
+        accels: &["<Primary><Shift>f"],
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Keybinding> {
+    KEYBINDINGS
+        .iter()
+        .find(|keybinding| keybinding.name == name)
+}
+
+/// 設定ファイルでは、ひとつなら文字列で、複数なら配列で書けます。空の配列で無効にできます。
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+pub enum Accels {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Accels {
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            Accels::One(accel) => vec![accel.clone()],
+            Accels::Many(accels) => accels.clone(),
+        }
+    }
+}
+
+/// `overrides`で置き換えていれば、そのアクセラレータを、無ければ既定値を返します。
+fn accels(
+    keybinding: &Keybinding,
+    overrides: &collections::BTreeMap<String, Accels>,
+) -> Vec<String> {
+    match overrides.get(keybinding.name) {
+        Some(accels) => accels.to_vec(),
+        None => keybinding
+            .accels
+            .iter()
+            .map(|accel| accel.to_string())
+            .collect(),
+    }
+}
+
+/// 既定値に`overrides`を重ねた、アクション名とアクセラレータの対応を返します。
+pub fn resolve(
+    overrides: &collections::BTreeMap<String, Accels>,
+) -> collections::BTreeMap<&'static str, Vec<String>> {
+    KEYBINDINGS
+        .iter()
+        .map(|keybinding| (keybinding.action, accels(keybinding, overrides)))
+        .collect()
+}
+
+/// `overrides`で置き換えたアクセラレータのうち、ほかのショートカットと重なるものを、
+/// 置き換えたショートカットの名前、アクセラレータ、重なる相手の名前の組で返します。
+pub fn find_conflicts(
+    overrides: &collections::BTreeMap<String, Accels>,
+) -> Vec<(String, String, &'static str)> {
+    let mut conflicts = vec![];
+    for (name, overridden) in overrides {
+        for accel in overridden.to_vec() {
+            let other = KEYBINDINGS
+                .iter()
+                .filter(|keybinding| keybinding.name != name)
+                .find(|keybinding| accels(keybinding, overrides).contains(&accel));
+            if let Some(other) = other {
+                conflicts.push((name.clone(), accel, other.name));
+            }
+        }
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_accels_do_not_conflict() {
+        let mut seen = collections::HashSet::new();
+        for keybinding in KEYBINDINGS {
+            for accel in keybinding.accels {
+                assert!(seen.insert(*accel), "{}", accel);
+            }
+        }
+    }
+
+    #[test]
+    fn find_conflicts_reports_overrides_used_elsewhere() {
+        let overrides = vec![
+            ("undo".to_string(), Accels::One("<Primary>n".to_string())),
+            (
+                "redo".to_string(),
+                Accels::Many(vec!["<Primary>y".to_string()]),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            find_conflicts(&overrides),
+            vec![("undo".to_string(), "<Primary>n".to_string(), "new-card")]
+        );
+    }
+}
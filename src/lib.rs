@@ -1 +1,8 @@
+pub mod card;
+pub mod config;
+pub mod i18n;
+pub mod keybindings;
 pub mod libs;
+pub mod state;
+pub mod theme;
+pub mod views;
@@ -1,7 +1,102 @@
 use gtk::prelude::*;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path;
 
 pub fn expand_path(path: &str) -> String {
-    return shellexpand::tilde(path).into_owned();
+    shellexpand::tilde(path).into_owned()
+}
+
+/// 英数字(日本語などを含みます)以外を`-`にまとめ、小文字にします。
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.trim_end_matches('-').to_string()
+}
+
+fn backup_path(path: &path::Path, generation: usize) -> path::PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    if generation == 1 {
+        backup.push(".bak");
+    } else {
+        backup.push(format!(".bak.{}", generation));
+    }
+    backup.into()
+}
+
+/// `path`のバックアップを1世代ずらし、今の内容を`.bak`にします。`.bak.2`以降が古い世代です。
+fn rotate_backups(path: &path::Path, backups: usize) -> io::Result<()> {
+    for generation in (1..backups).rev() {
+        let from = backup_path(path, generation);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, generation + 1))?;
+        }
+    }
+    let backup = backup_path(path, 1);
+    if backup.exists() {
+        fs::remove_file(&backup)?;
+    }
+    // `.bak`の更新日時を、ずらした日時にするため、ハードリンクではなくコピーします。
+    fs::copy(path, &backup).map(|_| ())
+}
+
+/// 自動保存のたびにずらすと、数秒前の内容しか残らないので、ファイルごとに1日に1回だけずらします。
+/// ずらした日は`.bak`の更新日時で調べるので、`coo card ...`のように別のプロセスで保存しても、同じ日にはずらしません。
+fn should_rotate(path: &path::Path, today: chrono::NaiveDate) -> bool {
+    match fs::metadata(backup_path(path, 1)).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => {
+            chrono::DateTime::<chrono::Local>::from(modified)
+                .naive_local()
+                .date()
+                != today
+        }
+        Err(_) => true,
+    }
+}
+
+/// 同じディレクトリの一時ファイルに書き込んでから置き換えるので、途中で落ちても`path`が壊れません。
+/// `backups`が1以上なら、置き換える前の内容をその世代数だけ残します。世代は1日に1回だけずらすので、
+/// `.bak`はその日に初めて保存する前の内容です。内容が変わらない場合は何もしません。
+pub fn write_atomically(path: &path::Path, content: &[u8], backups: usize) -> io::Result<()> {
+    let exists = match fs::read(path) {
+        Ok(current) if current == content => return Ok(()),
+        Ok(_) => true,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => false,
+        Err(error) => return Err(error),
+    };
+
+    let dir = path.parent().unwrap_or_else(|| path::Path::new("."));
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let today = chrono::Local::today().naive_local();
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        if exists && backups > 0 && should_rotate(path, today) {
+            rotate_backups(path, backups)?;
+        }
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    // 置き換えたことを確実に残すため、ディレクトリも書き出します。
+    if let Err(error) = fs::File::open(dir).and_then(|dir| dir.sync_all()) {
+        log::warn!("ディレクトリを書き出せません: {}: {}", dir.display(), error);
+    }
+
+    Ok(())
 }
 
 pub fn find_first_child_by_name<T: glib::IsA<gtk::Widget>>(
@@ -37,3 +132,95 @@ fn find_first_widget_by_name<T: glib::IsA<gtk::Widget>>(
     }
     None
 }
+
+/// テストごとに空のディレクトリを作ります。
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("coo-test-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_joins_words_with_hyphens() {
+        assert_eq!(slugify("My Cards!"), "my-cards");
+        assert_eq!(slugify("  日本語 の メモ "), "日本語-の-メモ");
+        assert_eq!(slugify("a -- b"), "a-b");
+        assert_eq!(slugify("!!!"), "");
+    }
+
+    #[test]
+    fn rotate_backups_shifts_generations() {
+        let dir = test_dir("rotate-backups");
+        let path = dir.join("a.toml");
+        fs::write(&path, "3").unwrap();
+        fs::write(backup_path(&path, 1), "2").unwrap();
+        fs::write(backup_path(&path, 2), "1").unwrap();
+
+        rotate_backups(&path, 2).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "3");
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "3");
+        assert_eq!(fs::read_to_string(backup_path(&path, 2)).unwrap(), "2");
+        assert!(!backup_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn write_atomically_without_backups() {
+        let dir = test_dir("write-without-backups");
+        let path = dir.join("a.toml");
+
+        write_atomically(&path, b"1", 0).unwrap();
+        write_atomically(&path, b"2", 0).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2");
+        assert!(!backup_path(&path, 1).exists());
+        // 一時ファイルは残しません。
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn write_atomically_rotates_backups_once_a_day() {
+        let dir = test_dir("write-with-backups");
+        let path = dir.join("a.toml");
+
+        write_atomically(&path, b"1", 2).unwrap();
+        assert!(!backup_path(&path, 1).exists());
+        write_atomically(&path, b"2", 2).unwrap();
+        write_atomically(&path, b"3", 2).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "3");
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "1");
+        assert!(!backup_path(&path, 2).exists());
+    }
+
+    #[test]
+    fn should_rotate_checks_backup_date() {
+        let dir = test_dir("should-rotate");
+        let path = dir.join("a.toml");
+        let today = chrono::Local::today().naive_local();
+        fs::write(&path, "1").unwrap();
+        assert!(should_rotate(&path, today));
+
+        rotate_backups(&path, 2).unwrap();
+
+        assert!(!should_rotate(&path, today));
+        assert!(should_rotate(&path, today.succ()));
+    }
+
+    #[test]
+    fn write_atomically_skips_unchanged_content() {
+        let dir = test_dir("write-unchanged");
+        let path = dir.join("a.toml");
+        fs::write(&path, "1").unwrap();
+
+        write_atomically(&path, b"1", 2).unwrap();
+
+        assert!(!backup_path(&path, 1).exists());
+    }
+}
@@ -4,93 +4,32 @@ use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use std::cell;
-use std::fs;
+use std::rc;
 
-mod views;
+use coo::i18n::{self, tr};
 
-struct View {
-    widget: gtk::Widget,
-    name: String,
-    title: String,
-}
-struct ViewsIterator {
-    index: usize,
-    views: Vec<toml::Value>,
-}
+mod actions;
+mod cli;
+mod config_error_window;
+mod main_window;
 
 const APPLICATION_NAME: &str = "Coo";
-
-impl ViewsIterator {
-    fn build_widget(&self, view_config: toml::Value) -> gtk::Widget {
-        let root = view_config["config"]["root"].as_str().unwrap();
-        match view_config["component"].as_str().unwrap() {
-            "assorted_card" => views::assorted_card::View::new(&coo::libs::expand_path(root))
-                .upcast::<gtk::Widget>(),
-            "files_and_file" => views::files_and_file::View::new(&coo::libs::expand_path(root))
-                .upcast::<gtk::Widget>(),
-            _ => panic!(),
-        }
-    }
-
-    fn new(config: &toml::Value) -> Self {
-        Self {
-            index: 0,
-            views: config["views"].as_array().unwrap().to_owned(),
+const DEFAULT_CONFIG_FILE_PATH: &str = "~/.config/coo.toml";
+
+fn bootstrap(application: &Coo) {
+    let config_file_path = application.config_file_path();
+    let config = match coo::config::load(&config_file_path, &application.view_registry()) {
+        Ok(config) => config,
+        Err(errors) => {
+            for error in &errors {
+                log::error!("{}", error);
+            }
+            config_error_window::show(application, &config_file_path, &errors);
+            return;
         }
-    }
-}
-
-impl Iterator for ViewsIterator {
-    type Item = View;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let view_config = self.views.get(self.index);
-        self.index += 1;
-        view_config.map(|config| View {
-            widget: self.build_widget(config.clone()),
-            name: uuid::Uuid::new_v4().to_string(),
-            title: config["title"].as_str().unwrap().to_string(),
-        })
-    }
-}
-
-fn bootstrap(application: &Coo, config_file_path: String) {
-    let header_bar = gtk::HeaderBarBuilder::new()
-        .title(APPLICATION_NAME)
-        .show_close_button(true)
-        .build();
-
-    let config = fs::read_to_string(config_file_path)
-        .unwrap()
-        .parse::<toml::Value>()
-        .unwrap();
-    let stack = gtk::StackBuilder::new().expand(true).build();
-    for view in ViewsIterator::new(&config) {
-        stack.add_titled(&view.widget, &view.name, &view.title);
-    }
-    header_bar.add(&gtk::StackSwitcherBuilder::new().stack(&stack).build());
-
-    let application_window = gtk::ApplicationWindowBuilder::new()
-        .application(application)
-        .title(APPLICATION_NAME)
-        .window_position(gtk::WindowPosition::Center)
-        .default_width(1280)
-        .default_height(720)
-        .build();
-    application_window.set_titlebar(Some(&header_bar));
-
-    application_window.add(&stack);
-
-    let css = include_str!("resources/coo.css").replace("{font}", config["font"].as_str().unwrap());
-    let css_provider = gtk::CssProvider::new();
-    css_provider.load_from_data(css.as_bytes()).unwrap();
-    gtk::StyleContext::add_provider_for_screen(
-        &gdk::Screen::default().expect("CSSプロバイダの初期化に失敗しました。"),
-        &css_provider,
-        gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
-    );
+    };
 
-    application_window.show_all();
+    main_window::MainWindow::new(application, &config_file_path, &config).show_all();
 }
 
 fn main() {
@@ -102,21 +41,74 @@ fn main() {
         glib::char::Char::from(b'c'),
         glib::OptionFlags::IN_MAIN,
         glib::OptionArg::String,
-        "設定ファイルを指定します。",
+        tr("設定ファイルを指定します。"),
         None,
     );
+    application.add_main_option(
+        "action",
+        glib::char::Char::from(b'a'),
+        glib::OptionFlags::IN_MAIN,
+        glib::OptionArg::String,
+        tr("起動中のCooでアクションを実行します。例: switch-view=cards"),
+        Some(tr("名前[=引数]")),
+    );
+    application.add_main_option(
+        "view",
+        glib::char::Char::from(0),
+        glib::OptionFlags::IN_MAIN,
+        glib::OptionArg::String,
+        tr("cardとmigrateで、assorted_cardのビューのIDを指定します。"),
+        Some("ID"),
+    );
+    application.add_main_option(
+        "date",
+        glib::char::Char::from(0),
+        glib::OptionFlags::IN_MAIN,
+        glib::OptionArg::String,
+        tr("cardで、日付を指定します。"),
+        Some("YYYY-MM-DD"),
+    );
+    application.add_main_option(
+        "key",
+        glib::char::Char::from(0),
+        glib::OptionFlags::IN_MAIN,
+        glib::OptionArg::String,
+        tr("card addで、カードのキーを指定します。"),
+        Some(tr("キー")),
+    );
+    application.add_main_option(
+        "week",
+        glib::char::Char::from(0),
+        glib::OptionFlags::IN_MAIN,
+        glib::OptionArg::None,
+        tr("card listで、その日を含む週のカードを表示します。"),
+        None,
+    );
+    application.add_main_option(
+        cli::REMAINING,
+        glib::char::Char::from(0),
+        glib::OptionFlags::IN_MAIN,
+        glib::OptionArg::StringArray,
+        "",
+        Some(tr("[card <サブコマンド> | migrate] [引数…]")),
+    );
     application.run();
 }
 
 #[derive(Debug)]
 pub struct CooExt {
     config_file_path: cell::RefCell<String>,
+    view_registry: rc::Rc<coo::views::ViewRegistry>,
+    /// 最初に起動したCooに`--action`が指定されたときに、ウィンドウを作った後で実行するアクションです。
+    pending_action: cell::RefCell<Option<(String, Option<glib::Variant>)>>,
 }
 
 impl Default for CooExt {
     fn default() -> Self {
         Self {
-            config_file_path: cell::RefCell::new(coo::libs::expand_path("~/.config/coo.toml")),
+            config_file_path: cell::RefCell::new(coo::libs::expand_path(DEFAULT_CONFIG_FILE_PATH)),
+            view_registry: rc::Rc::new(coo::views::ViewRegistry::default()),
+            pending_action: cell::RefCell::new(None),
         }
     }
 }
@@ -131,11 +123,23 @@ impl ObjectSubclass for CooExt {
 impl ObjectImpl for CooExt {}
 
 impl ApplicationImpl for CooExt {
+    fn startup(&self, application: &Self::Type) {
+        self.parent_startup(application);
+
+        actions::register(application);
+    }
+
     fn activate(&self, application: &Self::Type) {
         self.parent_activate(application);
 
-        let coo = application.downcast_ref::<Coo>().unwrap();
-        bootstrap(coo, self.config_file_path.borrow().clone());
+        // 2つ目のCooを起動したときにも呼ばれるので、ウィンドウがあれば新しく作りません。
+        match application.active_window() {
+            Some(window) => window.present(),
+            None => bootstrap(application),
+        }
+        if let Some((name, parameter)) = self.pending_action.take() {
+            application.activate_action(&name, parameter.as_ref());
+        }
     }
 
     fn handle_local_options(&self, application: &Self::Type, options: &glib::VariantDict) -> i32 {
@@ -143,6 +147,33 @@ impl ApplicationImpl for CooExt {
             self.config_file_path
                 .replace(coo::libs::expand_path(&variant.get::<String>().unwrap()));
         }
+        // `coo card ...`のようなコマンドは、ウィンドウを開かずに実行して終了します。
+        if let Some(invocation) = cli::parse(options) {
+            return cli::run(invocation, &self.config_file_path.borrow());
+        }
+        if let Some(variant) = options.lookup_value("action", None) {
+            if let Err(error) = application.register(gio::NONE_CANCELLABLE) {
+                eprintln!(
+                    "{}",
+                    i18n::format("Cooを登録できませんでした: {}", &[&error])
+                );
+                return 1;
+            }
+            // 登録すると、起動中のCooのアクションも調べられるようになります。
+            let (name, parameter) =
+                match actions::parse_action(application, &variant.get::<String>().unwrap()) {
+                    Ok(action) => action,
+                    Err(message) => {
+                        eprintln!("{}", message);
+                        return 1;
+                    }
+                };
+            if application.is_remote() {
+                application.activate_action(&name, parameter.as_ref());
+                return 0;
+            }
+            self.pending_action.replace(Some((name, parameter)));
+        }
         self.parent_handle_local_options(application, options)
     }
 }
@@ -151,7 +182,8 @@ impl GtkApplicationImpl for CooExt {}
 
 glib::wrapper! {
     pub struct Coo(ObjectSubclass<CooExt>)
-        @extends gio::Application, gtk::Application;
+        @extends gio::Application, gtk::Application,
+        @implements gio::ActionMap, gio::ActionGroup;
 }
 
 impl Coo {
@@ -161,6 +193,23 @@ impl Coo {
             ("application-id", &"com.varwww.coo"),
             ("flags", &ApplicationFlags::empty()),
         ])
-        .expect("Cooの起動に失敗しました。")
+        .unwrap_or_else(|_| panic!("{}", tr("Cooの起動に失敗しました。")))
+    }
+
+    fn config_file_path(&self) -> String {
+        CooExt::from_instance(self)
+            .config_file_path
+            .borrow()
+            .clone()
+    }
+
+    fn view_registry(&self) -> rc::Rc<coo::views::ViewRegistry> {
+        CooExt::from_instance(self).view_registry.clone()
+    }
+
+    fn set_config_file_path(&self, path: &str) {
+        CooExt::from_instance(self)
+            .config_file_path
+            .replace(path.to_string());
     }
 }
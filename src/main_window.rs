@@ -0,0 +1,532 @@
+use gio::prelude::*;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use std::cell;
+use std::collections;
+use std::fs;
+use std::io;
+use std::path;
+
+use coo::i18n::{self, tr};
+
+#[derive(Debug)]
+pub struct MainWindowExt {
+    header_bar: gtk::HeaderBar,
+    info_bar: gtk::InfoBar,
+    stack: cell::RefCell<gtk::Stack>,
+    css_provider: gtk::CssProvider,
+    /// 組み込みのCSSに重ねる、ユーザーのCSSです。
+    user_css_provider: gtk::CssProvider,
+    config_file_path: cell::RefCell<String>,
+    monitor: cell::RefCell<Option<gio::FileMonitor>>,
+    user_css_monitor: cell::RefCell<Option<gio::FileMonitor>>,
+    views: cell::RefCell<Vec<coo::config::ViewConfig>>,
+    /// 起動したときのGTKのダークテーマの設定です。`theme.prefer_dark`が無ければ、これに戻します。
+    default_prefer_dark: cell::Cell<Option<bool>>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for MainWindowExt {
+    const NAME: &'static str = "CooMainWindow";
+    type Type = MainWindow;
+    type ParentType = gtk::ApplicationWindow;
+
+    fn new() -> Self {
+        let header_bar = gtk::HeaderBarBuilder::new()
+            .title(crate::APPLICATION_NAME)
+            .show_close_button(true)
+            .build();
+        let info_bar = gtk::InfoBarBuilder::new()
+            .message_type(gtk::MessageType::Error)
+            .show_close_button(true)
+            .no_show_all(true)
+            .build();
+        info_bar.connect_response(|info_bar, _| info_bar.hide());
+
+        Self {
+            header_bar,
+            info_bar,
+            stack: cell::RefCell::new(gtk::StackBuilder::new().expand(true).build()),
+            css_provider: gtk::CssProvider::new(),
+            user_css_provider: gtk::CssProvider::new(),
+            config_file_path: cell::RefCell::new(String::new()),
+            monitor: cell::RefCell::new(None),
+            user_css_monitor: cell::RefCell::new(None),
+            views: cell::RefCell::new(vec![]),
+            default_prefer_dark: cell::Cell::new(None),
+        }
+    }
+}
+
+impl ObjectImpl for MainWindowExt {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.set_titlebar(Some(&self.header_bar));
+        let root = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+        root.add(&self.info_bar);
+        root.add(&self.stack.borrow().clone());
+        obj.add(&root);
+
+        let screen = gdk::Screen::default()
+            .unwrap_or_else(|| panic!("{}", tr("CSSプロバイダの初期化に失敗しました。")));
+        gtk::StyleContext::add_provider_for_screen(
+            &screen,
+            &self.css_provider,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+        gtk::StyleContext::add_provider_for_screen(
+            &screen,
+            &self.user_css_provider,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+        );
+    }
+}
+
+impl WidgetImpl for MainWindowExt {}
+impl ContainerImpl for MainWindowExt {}
+impl BinImpl for MainWindowExt {}
+impl WindowImpl for MainWindowExt {}
+impl ApplicationWindowImpl for MainWindowExt {}
+
+glib::wrapper! {
+    pub struct MainWindow(ObjectSubclass<MainWindowExt>)
+        @extends gtk::Widget, gtk::Container, gtk::Bin, gtk::Window, gtk::ApplicationWindow,
+        @implements gio::ActionMap, gio::ActionGroup;
+}
+
+impl MainWindow {
+    pub fn new(
+        application: &crate::Coo,
+        config_file_path: &str,
+        config: &coo::config::Config,
+    ) -> Self {
+        let this: Self = glib::Object::new(&[
+            ("application", application),
+            ("title", &crate::APPLICATION_NAME),
+            ("window-position", &gtk::WindowPosition::Center),
+            ("default-width", &1280),
+            ("default-height", &720),
+        ])
+        .unwrap_or_else(|_| panic!("{}", tr("メインウィンドウの作成に失敗しました。")));
+        this.get_ext()
+            .config_file_path
+            .replace(config_file_path.to_string());
+
+        this.add_view_actions();
+        this.apply_config(config);
+        this.restore_state(&coo::state::load());
+        this.watch_config_file();
+        this.load_user_css();
+        this.watch_user_css();
+        this.connect_delete_event(|this, _| {
+            this.flush_views();
+            this.save_state();
+            gtk::Inhibit(false)
+        });
+
+        this
+    }
+
+    fn get_ext(&self) -> &MainWindowExt {
+        MainWindowExt::from_instance(self)
+    }
+
+    fn coo(&self) -> crate::Coo {
+        self.application()
+            .and_then(|application| application.downcast().ok())
+            .unwrap_or_else(|| panic!("{}", tr("メインウィンドウがCooに属していません。")))
+    }
+
+    /// 設定に合わせてビューとCSSを作り直します。表示中のビューは、作り直した後も表示し続けます。
+    fn apply_config(&self, config: &coo::config::Config) {
+        // ビューを作る前に、言語を決めておきます。
+        i18n::select(config.locale);
+        let ext = self.get_ext();
+
+        self.flush_views();
+        let old_stack = ext.stack.borrow().clone();
+        let visible_child_name = old_stack.visible_child_name();
+        let view_states = self.view_states();
+
+        let registry = self.coo().view_registry();
+        let stack = gtk::StackBuilder::new().expand(true).build();
+        for view in &config.views {
+            match registry.build(&view.component, &view.config) {
+                Ok(widget) => stack.add_titled(&widget, &view.id, &view.title),
+                Err(error) => log::error!("{}を作成できませんでした: {}", view.title, error),
+            }
+        }
+        ext.views.replace(config.views.clone());
+
+        for child in ext.header_bar.children() {
+            if child.is::<gtk::StackSwitcher>() {
+                ext.header_bar.remove(&child);
+            }
+        }
+        ext.header_bar
+            .add(&gtk::StackSwitcherBuilder::new().stack(&stack).build());
+        ext.header_bar.show_all();
+
+        let root = old_stack.parent().unwrap().downcast::<gtk::Box>().unwrap();
+        root.remove(&old_stack);
+        root.add(&stack);
+        ext.stack.replace(stack.clone());
+        stack.show_all();
+        if let Some(name) = visible_child_name {
+            if stack.child_by_name(&name).is_some() {
+                stack.set_visible_child_name(&name);
+            }
+        }
+        self.restore_view_states(view_states);
+
+        let css = config.theme.css(&config.font);
+        if let Err(error) = ext.css_provider.load_from_data(css.as_bytes()) {
+            log::error!("CSSを読み込めませんでした: {}", error);
+        }
+        if let Some(settings) = gtk::Settings::default() {
+            // gtkのpreludeではSettingsExtの名前が重なって使えないので、プロパティの名前で設定します。
+            const PREFER_DARK: &str = "gtk-application-prefer-dark-theme";
+            let default_prefer_dark = match ext.default_prefer_dark.get() {
+                Some(prefer_dark) => prefer_dark,
+                None => {
+                    let prefer_dark = settings
+                        .property(PREFER_DARK)
+                        .ok()
+                        .and_then(|value| value.get::<bool>().ok())
+                        .unwrap_or(false);
+                    ext.default_prefer_dark.set(Some(prefer_dark));
+                    prefer_dark
+                }
+            };
+            let prefer_dark = config.theme.prefer_dark.unwrap_or(default_prefer_dark);
+            if let Err(error) = settings.set_property(PREFER_DARK, prefer_dark) {
+                log::warn!("ダークテーマを設定できません: {}", error);
+            }
+        }
+
+        self.apply_keybindings(config);
+    }
+
+    fn apply_keybindings(&self, config: &coo::config::Config) {
+        let application = self.coo();
+        for (action, accels) in coo::keybindings::resolve(&config.keybindings) {
+            let accels = accels
+                .iter()
+                .map(String::as_str)
+                .filter(|accel| {
+                    let (key, _) = gtk::accelerator_parse(accel);
+                    if key == 0 {
+                        log::warn!("アクセラレータを解釈できません: {}", accel);
+                    }
+                    key != 0
+                })
+                .collect::<Vec<&str>>();
+            application.set_accels_for_action(action, &accels);
+        }
+    }
+
+    fn add_view_actions(&self) {
+        let next_view = gio::SimpleAction::new("next-view", None);
+        next_view.connect_activate(glib::clone!(@weak self as this => move |_, _| {
+            this.select_view_by_offset(1);
+        }));
+        self.add_action(&next_view);
+
+        let previous_view = gio::SimpleAction::new("previous-view", None);
+        previous_view.connect_activate(glib::clone!(@weak self as this => move |_, _| {
+            this.select_view_by_offset(-1);
+        }));
+        self.add_action(&previous_view);
+
+        let select_view = gio::SimpleAction::new("select-view", Some(&i32::static_variant_type()));
+        select_view.connect_activate(glib::clone!(@weak self as this => move |_, parameter| {
+            if let Some(index) = parameter.and_then(|parameter| parameter.get::<i32>()) {
+                this.select_view(index);
+            }
+        }));
+        self.add_action(&select_view);
+    }
+
+    /// `index`番目のビューを表示します。
+    fn select_view(&self, index: i32) {
+        let stack = self.get_ext().stack.borrow();
+        if let Some(child) = stack.children().get(index as usize) {
+            stack.set_visible_child(child);
+        }
+    }
+
+    /// 表示中のビューから`offset`だけ離れたビューを表示します。端を越えると反対の端に戻ります。
+    fn select_view_by_offset(&self, offset: i32) {
+        let stack = self.get_ext().stack.borrow();
+        let children = stack.children();
+        if children.is_empty() {
+            return;
+        }
+        let current = stack
+            .visible_child()
+            .and_then(|visible| children.iter().position(|child| child == &visible))
+            .unwrap_or(0) as i32;
+        let next = (current + offset).rem_euclid(children.len() as i32);
+        stack.set_visible_child(&children[next as usize]);
+    }
+
+    /// IDが`id`のビューを表示します。
+    pub fn show_view(&self, id: &str) {
+        let stack = self.get_ext().stack.borrow();
+        if stack.child_by_name(id).is_some() {
+            stack.set_visible_child_name(id);
+        } else {
+            log::warn!("ビューがありません: {}", id);
+        }
+    }
+
+    /// `T`のビューを表示して返します。表示中のビューが`T`であればそれを、そうでなければ最初の`T`を選びます。
+    fn show_view_of<T: IsA<gtk::Widget>>(&self) -> Option<T> {
+        let stack = self.get_ext().stack.borrow();
+        let view = stack
+            .visible_child()
+            .and_then(|child| child.downcast::<T>().ok())
+            .or_else(|| {
+                stack
+                    .children()
+                    .into_iter()
+                    .find_map(|child| child.downcast::<T>().ok())
+            });
+        match view {
+            Some(ref view) => stack.set_visible_child(view),
+            None => log::warn!("{}のビューがありません。", T::static_type().name()),
+        }
+
+        view
+    }
+
+    pub fn goto_date(&self, date: chrono::NaiveDate) {
+        if let Some(view) = self.show_view_of::<coo::views::assorted_card::View>() {
+            view.set_date(date);
+        }
+    }
+
+    pub fn open_file(&self, path: &str) {
+        if let Some(view) = self.show_view_of::<coo::views::files_and_file::View>() {
+            view.open_file(path);
+        }
+    }
+
+    /// 今日の最後にカードを追加します。
+    pub fn add_card(&self, text: &str) {
+        if let Some(view) = self.show_view_of::<coo::views::assorted_card::View>() {
+            view.add_card(chrono::Local::today().naive_local(), text);
+        }
+    }
+
+    /// ビューのIDごとに、各ビューの`ViewFactory`が返した状態を集めます。
+    fn view_states(&self) -> collections::BTreeMap<String, toml::Value> {
+        let ext = self.get_ext();
+        let registry = self.coo().view_registry();
+        let stack = ext.stack.borrow();
+
+        ext.views
+            .borrow()
+            .iter()
+            .filter_map(|view| {
+                let widget = stack.child_by_name(&view.id)?;
+                Some((
+                    view.id.clone(),
+                    registry.save_state(&view.component, &widget)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// 各ビューの保存を待っている変更を、すぐに保存します。
+    fn flush_views(&self) {
+        let ext = self.get_ext();
+        let registry = self.coo().view_registry();
+        let stack = ext.stack.borrow();
+
+        for view in ext.views.borrow().iter() {
+            if let Some(widget) = stack.child_by_name(&view.id) {
+                registry.flush(&view.component, &widget);
+            }
+        }
+    }
+
+    fn restore_view_states(&self, mut states: collections::BTreeMap<String, toml::Value>) {
+        let ext = self.get_ext();
+        let registry = self.coo().view_registry();
+        let stack = ext.stack.borrow();
+
+        for view in ext.views.borrow().iter() {
+            if let (Some(widget), Some(state)) =
+                (stack.child_by_name(&view.id), states.remove(&view.id))
+            {
+                registry.restore_state(&view.component, &widget, state);
+            }
+        }
+    }
+
+    fn restore_state(&self, state: &coo::state::State) {
+        if let (Some(width), Some(height)) = (state.window.width, state.window.height) {
+            self.set_default_size(width, height);
+        }
+        if let (Some(x), Some(y)) = (state.window.x, state.window.y) {
+            self.move_(x, y);
+        }
+        if state.window.maximized {
+            self.maximize();
+        }
+
+        if let Some(ref name) = state.active_view {
+            let stack = self.get_ext().stack.borrow();
+            if stack.child_by_name(name).is_some() {
+                stack.set_visible_child_name(name);
+            }
+        }
+        self.restore_view_states(state.views.clone());
+    }
+
+    /// 現在の状態を状態ファイルに保存します。今の設定に無いビューの状態は、以前のものを残します。
+    fn save_state(&self) {
+        let mut state = coo::state::load();
+
+        state.window.maximized = self.is_maximized();
+        // 最大化を解除したときに元の大きさに戻せるように、最大化中の大きさは保存しません。
+        if !state.window.maximized {
+            let (width, height) = self.size();
+            let (x, y) = self.position();
+            state.window.width = Some(width);
+            state.window.height = Some(height);
+            state.window.x = Some(x);
+            state.window.y = Some(y);
+        }
+        state.active_view = self
+            .get_ext()
+            .stack
+            .borrow()
+            .visible_child_name()
+            .map(|name| name.to_string());
+        state.views.extend(self.view_states());
+
+        if let Err(error) = coo::state::save(&state) {
+            log::error!("状態ファイルを保存できませんでした: {}", error);
+        }
+    }
+
+    fn show_config_errors(&self, errors: &[coo::config::ConfigError]) {
+        let info_bar = &self.get_ext().info_bar;
+        let content_area = info_bar.content_area();
+        for child in content_area.children() {
+            content_area.remove(&child);
+        }
+        let vbox = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+        vbox.add(&gtk::Label::new(Some(tr(
+            "設定ファイルを読み込み直せませんでした。以前の設定で表示しています。",
+        ))));
+        for error in errors {
+            vbox.add(
+                &gtk::LabelBuilder::new()
+                    .label(&error.to_string())
+                    .selectable(true)
+                    .xalign(0.0)
+                    .build(),
+            );
+        }
+        content_area.add(&vbox);
+        vbox.show_all();
+        info_bar.show();
+    }
+
+    /// 設定ファイルを読み込み直します。問題があれば、現在の表示を保ったまま問題を表示します。
+    pub fn reload_config(&self) {
+        let config_file_path = self.get_ext().config_file_path.borrow().clone();
+        match coo::config::load(&config_file_path, &self.coo().view_registry()) {
+            Ok(config) => {
+                log::debug!("設定ファイルを読み込み直しました: {}", config_file_path);
+                self.get_ext().info_bar.hide();
+                self.apply_config(&config);
+            }
+            Err(errors) => {
+                for error in &errors {
+                    log::error!("{}", error);
+                }
+                self.show_config_errors(&errors);
+            }
+        }
+    }
+
+    fn watch_config_file(&self) {
+        let ext = self.get_ext();
+        let monitor = watch_file(
+            path::Path::new(&*ext.config_file_path.borrow()),
+            glib::clone!(@weak self as this => move |event| {
+                // 保存の途中で一度消えることがあるので、消えただけでは以前の設定のままにします。
+                if event != gio::FileMonitorEvent::Deleted {
+                    this.reload_config();
+                }
+            }),
+        );
+        ext.monitor.replace(monitor);
+    }
+
+    /// ユーザーのCSSを読み込みます。ファイルが無ければ、組み込みのCSSだけを使います。
+    fn load_user_css(&self) {
+        let path = coo::theme::user_css_path();
+        let css = match fs::read_to_string(&path) {
+            Ok(css) => css,
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    log::warn!("CSSを読み込めません: {}: {}", path.display(), error);
+                }
+                String::new()
+            }
+        };
+        if let Err(error) = self
+            .get_ext()
+            .user_css_provider
+            .load_from_data(css.as_bytes())
+        {
+            log::error!("CSSを読み込めませんでした: {}: {}", path.display(), error);
+        }
+    }
+
+    fn watch_user_css(&self) {
+        let monitor = watch_file(
+            &coo::theme::user_css_path(),
+            glib::clone!(@weak self as this => move |_| this.load_user_css()),
+        );
+        self.get_ext().user_css_monitor.replace(monitor);
+    }
+}
+
+/// `path`が書き換えられる、作られる、または消されるたびに`on_changed`を呼びます。
+fn watch_file<F: Fn(gio::FileMonitorEvent) + 'static>(
+    path: &path::Path,
+    on_changed: F,
+) -> Option<gio::FileMonitor> {
+    let file = gio::File::for_path(path);
+    let monitor = match file.monitor_file(gio::FileMonitorFlags::NONE, gio::NONE_CANCELLABLE) {
+        Ok(monitor) => monitor,
+        Err(error) => {
+            log::warn!("変更を監視できません: {}: {}", path.display(), error);
+            return None;
+        }
+    };
+    monitor.connect_changed(move |_, _, _, event| {
+        // 書き込みの途中で読み込まないように、書き込みが終わった時点と置き換えられた時点でのみ読み込みます。
+        if matches!(
+            event,
+            gio::FileMonitorEvent::ChangesDoneHint
+                | gio::FileMonitorEvent::Created
+                | gio::FileMonitorEvent::Deleted
+        ) {
+            on_changed(event);
+        }
+    });
+
+    Some(monitor)
+}
@@ -0,0 +1,75 @@
+//! 終了時の画面の状態を保存し、次の起動時に復元するための状態ファイルを扱います。
+use std::collections;
+use std::env;
+use std::fs;
+use std::io;
+use std::path;
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct State {
+    /// 表示していたビューのIDです。
+    pub active_view: Option<String>,
+    pub window: WindowState,
+    /// ビューのIDごとの状態です。中身は各ビューの`ViewFactory`が決めます。
+    pub views: collections::BTreeMap<String, toml::Value>,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct WindowState {
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub maximized: bool,
+}
+
+/// `$XDG_STATE_HOME/coo/state.toml`を返します。`$XDG_STATE_HOME`が無ければ`~/.local/state`を使います。
+pub fn state_file_path() -> path::PathBuf {
+    let state_home = env::var("XDG_STATE_HOME")
+        .ok()
+        .filter(|dir| path::Path::new(dir).is_absolute())
+        .unwrap_or_else(|| crate::libs::expand_path("~/.local/state"));
+
+    path::Path::new(&state_home).join("coo").join("state.toml")
+}
+
+/// 状態ファイルを読み込みます。無い、または読めない場合は初期状態を返します。
+pub fn load() -> State {
+    let path = state_file_path();
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(error) => {
+            if error.kind() != io::ErrorKind::NotFound {
+                log::warn!(
+                    "状態ファイルを読み込めません: {}: {}",
+                    path.display(),
+                    error
+                );
+            }
+            return State::default();
+        }
+    };
+
+    toml::from_str(&content).unwrap_or_else(|error| {
+        log::warn!(
+            "状態ファイルを解釈できません: {}: {}",
+            path.display(),
+            error
+        );
+        State::default()
+    })
+}
+
+pub fn save(state: &State) -> io::Result<()> {
+    let path = state_file_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content = toml::to_string_pretty(state)
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+    log::debug!("状態ファイル: {}, 保存内容:\n{}", path.display(), content);
+
+    fs::write(path, content)
+}
@@ -0,0 +1,70 @@
+//! 設定ファイルの`[theme]`と、組み込みのCSSに重ねるユーザーのCSSファイルを扱います。
+use std::collections;
+use std::env;
+use std::path;
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// カードの文字の大きさ(px)です。
+    pub font_size: u32,
+    /// 日と日の間の余白(px)です。
+    pub column_spacing: u32,
+    /// ダークテーマを使うかです。無ければGTKの設定に従います。
+    pub prefer_dark: Option<bool>,
+    /// カードのキーごとの、カードの背景色です。
+    pub key_colors: collections::BTreeMap<String, String>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            font_size: 18,
+            column_spacing: 4,
+            prefer_dark: None,
+            key_colors: collections::BTreeMap::new(),
+        }
+    }
+}
+
+/// カードのキーから、そのキーのカードに付けるCSSのクラス名を作ります。キーは絵文字なので、コードポイントを使います。
+pub fn key_class(key: &str) -> String {
+    let code_points = key
+        .chars()
+        .map(|c| format!("{:x}", c as u32))
+        .collect::<Vec<String>>();
+
+    format!("card-key-{}", code_points.join("-"))
+}
+
+impl Theme {
+    /// 組み込みのCSSに、`font`とこのテーマを埋め込みます。
+    pub fn css(&self, font: &str) -> String {
+        let mut css = include_str!("resources/coo.css")
+            .replace("{font}", font)
+            .replace("{font_size}", &self.font_size.to_string())
+            .replace(
+                "{column_margin}",
+                &(self.column_spacing as f64 / 2.0).to_string(),
+            );
+        for (key, color) in &self.key_colors {
+            css.push_str(&format!(
+                "\n.card.{} {{\n    background-color: {};\n}}\n",
+                key_class(key),
+                color
+            ));
+        }
+
+        css
+    }
+}
+
+/// `$XDG_CONFIG_HOME/coo/coo.css`を返します。`$XDG_CONFIG_HOME`が無ければ`~/.config`を使います。
+pub fn user_css_path() -> path::PathBuf {
+    let config_home = env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|dir| path::Path::new(dir).is_absolute())
+        .unwrap_or_else(|| crate::libs::expand_path("~/.config"));
+
+    path::Path::new(&config_home).join("coo").join("coo.css")
+}
@@ -3,88 +3,299 @@ use gio::prelude::*;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use std::cell;
-use std::fs;
-use std::io::prelude::*;
+use std::collections;
 use std::rc;
-use std::sync;
+
+use crate::card::{self, Card, DailyBucket};
+use crate::i18n::{self, tr};
+use crate::theme;
 
 #[derive(Debug)]
 pub struct ViewExt {
     date: cell::RefCell<chrono::NaiveDate>,
     widget: cell::RefCell<gtk::Grid>,
     path: cell::RefCell<String>,
+    config: cell::RefCell<Config>,
+    keys: cell::RefCell<rc::Rc<Keys>>,
+    /// 週の代わりに、月の一覧を表示しているかです。
+    month_mode: cell::Cell<bool>,
+    /// 最後に持ち越した日です。自動の持ち越しを1日に1回にするために使います。
+    last_carry_over: cell::Cell<Option<chrono::NaiveDate>>,
+    /// 保存を待っている変更です。保存先ごとに、最後の変更を保存する処理を持ちます。
+    pending_saves: cell::RefCell<Vec<PendingSave>>,
+    /// 前の変更で始めたタイマーが、後の変更を待たずに保存しないように数えます。
+    save_generation: cell::Cell<u64>,
+    undo_stack: cell::RefCell<Vec<Vec<Change>>>,
+    redo_stack: cell::RefCell<Vec<Vec<Change>>>,
+    /// カードを消したときに、元に戻せることを知らせます。
+    toast: gtk::InfoBar,
+    /// 前のトーストを閉じるタイマーが、新しいトーストを閉じないように数えます。
+    toast_generation: cell::Cell<u64>,
+    /// 表示中の日と週のメモです。日は日付の順に並べ、週のメモは最後に置きます。
+    columns: cell::RefCell<Vec<Column>>,
+    /// 保存できなかったカードです。保存できるまで、ファイルの代わりに表示します。
+    unsaved: cell::RefCell<collections::BTreeMap<BucketKey, Vec<Card>>>,
+    /// 表示中のファイルの、最後に読み込んだか保存した内容です。外部での変更を見つけるために使います。
+    known: cell::RefCell<collections::BTreeMap<BucketKey, Vec<Card>>>,
+    monitors: cell::RefCell<Vec<gio::FileMonitor>>,
+    search: Search,
+    index: cell::RefCell<card::CardIndex>,
+    /// 検索結果の行ごとの、カードの日付とIDです。
+    search_hits: cell::RefCell<Vec<(chrono::NaiveDate, uuid::Uuid)>>,
 }
 
-static WIDGET_NAME_CARD_TEXT: &str = "card-text";
-static WIDGET_NAME_CARD_KEY: &str = "card-key";
-static WIDGET_NAME_CARD: &str = "card";
-
-#[derive(Debug, serde::Serialize)]
-struct Card {
-    key: String,
-    text: String,
+/// 週の上に出す、すべての日のカードを探す検索バーです。
+#[derive(Debug)]
+struct Search {
+    bar: gtk::SearchBar,
+    entry: gtk::SearchEntry,
+    /// 空のIDは、すべてのキーです。
+    key: gtk::ComboBoxText,
+    results: gtk::ListBox,
+    scrolled_window: gtk::ScrolledWindow,
 }
 
-impl Card {
-    fn new(key: String, text: String) -> Self {
-        Self { key, text }
+impl Search {
+    fn new() -> Self {
+        let entry = gtk::SearchEntryBuilder::new()
+            .placeholder_text(tr("カードを検索"))
+            .hexpand(true)
+            .build();
+        let key = gtk::ComboBoxText::new();
+        let hbox = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(4)
+            .build();
+        hbox.add(&entry);
+        hbox.add(&key);
+        let bar = gtk::SearchBarBuilder::new().show_close_button(true).build();
+        bar.add(&hbox);
+        bar.connect_entry(&entry);
+
+        let results = gtk::ListBoxBuilder::new()
+            .selection_mode(gtk::SelectionMode::Browse)
+            .build();
+        results.set_placeholder(Some(&gtk::Label::new(Some(tr("見つかりませんでした。")))));
+        let scrolled_window = gtk::ScrolledWindowBuilder::new()
+            .min_content_height(200)
+            .no_show_all(true)
+            .build();
+        scrolled_window.add(&results);
+
+        Self {
+            bar,
+            entry,
+            key,
+            results,
+            scrolled_window,
+        }
     }
 }
 
-#[derive(Debug, serde::Serialize)]
-struct DailyBucket {
-    version: usize,
-    date: chrono::NaiveDate,
-    cards: Vec<Card>,
+/// カードを保存するファイルです。日のカードと週のメモを、同じように読み込み、保存します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum BucketKey {
+    Day(chrono::NaiveDate),
+    Week(chrono::IsoWeek),
 }
 
-impl DailyBucket {
-    fn new(date: NaiveDate, cards: Vec<Card>) -> Self {
-        Self {
-            version: 1,
-            date,
-            cards,
+impl BucketKey {
+    fn file_path(self, root: &str) -> String {
+        match self {
+            BucketKey::Day(date) => card::bucket_file_path(root, date),
+            BucketKey::Week(week) => card::weekly_bucket_file_path(root, week),
+        }
+    }
+
+    fn load(self, root: &str) -> Result<Vec<Card>, card::StorageError> {
+        match self {
+            BucketKey::Day(date) => card::load_daily_bucket(root, date).map(|bucket| bucket.cards),
+            BucketKey::Week(week) => {
+                card::load_weekly_bucket(root, week).map(|bucket| bucket.cards)
+            }
+        }
+    }
+
+    fn save(self, root: &str, cards: Vec<Card>, backups: usize) -> Result<(), card::StorageError> {
+        match self {
+            BucketKey::Day(date) => {
+                card::save_daily_bucket(root, &DailyBucket::new(date, cards), backups)
+            }
+            BucketKey::Week(week) => {
+                card::save_weekly_bucket(root, &card::WeeklyBucket::new(week, cards), backups)
+            }
+        }
+    }
+
+    /// このファイルを表示するために、表示する日です。週なら、週の最初の曜日によらずその週に入る木曜日にします。
+    fn date(self) -> chrono::NaiveDate {
+        match self {
+            BucketKey::Day(date) => date,
+            BucketKey::Week(week) => {
+                chrono::NaiveDate::from_isoywd(week.year(), week.week(), chrono::Weekday::Thu)
+            }
         }
     }
 }
 
-fn compute_last_monday(today: chrono::NaiveDate) -> chrono::NaiveDate {
-    for i in 1..7 {
-        let date = today - chrono::Duration::days(i);
-        if date.weekday() == chrono::Weekday::Mon {
-            return date;
+/// 元に戻せる、あるファイルのカードの変更です。まとめて元に戻す変更を`Vec<Change>`にします。
+#[derive(Debug, Clone)]
+struct Change {
+    key: BucketKey,
+    before: Vec<Card>,
+    after: Vec<Card>,
+}
+
+struct PendingSave {
+    key: BucketKey,
+    save: Save,
+}
+
+impl std::fmt::Debug for PendingSave {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("PendingSave")
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 最後の変更から、この時間だけ待ってから保存します。
+const SAVE_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// 初めて自動で持ち越すときに、何日前まで見るかです。
+const AUTO_CARRY_OVER_DAYS: i64 = 30;
+
+/// 元に戻せる変更の数です。
+const UNDO_LIMIT: usize = 100;
+
+#[derive(Debug)]
+struct Column {
+    key: BucketKey,
+    list_box: gtk::ListBox,
+    /// 読み込みや保存に失敗したことを表示します。
+    info_bar: gtk::InfoBar,
+}
+
+static WIDGET_NAME_CARD_TEXT: &str = "card-text";
+static WIDGET_NAME_CARD_KEY: &str = "card-key";
+static WIDGET_NAME_CARD: &str = "card";
+/// ドラッグで運ぶカードの種類です。中身はカードのIDです。
+static DRAG_TARGET_CARD: &str = "application/x-coo-card";
+
+fn drag_targets() -> Vec<gtk::TargetEntry> {
+    vec![gtk::TargetEntry::new(
+        DRAG_TARGET_CARD,
+        gtk::TargetFlags::SAME_APP,
+        0,
+    )]
+}
+
+/// ビューで使うキーと、キーごとの背景色のCSSです。
+#[derive(Debug)]
+struct Keys {
+    keys: card::CardKeys,
+    /// テーマの`key_colors`より優先するように、カードごとに追加します。
+    css_provider: gtk::CssProvider,
+}
+
+impl Keys {
+    fn new(keys: card::CardKeys) -> Self {
+        let css = keys
+            .iter()
+            .filter_map(|key| {
+                let color = key.color.as_ref()?;
+                Some(format!(
+                    ".card.{} {{\n    background-color: {};\n}}\n",
+                    theme::key_class(&key.symbol),
+                    color
+                ))
+            })
+            .collect::<String>();
+        let css_provider = gtk::CssProvider::new();
+        if let Err(error) = css_provider.load_from_data(css.as_bytes()) {
+            log::error!("キーの色を読み込めませんでした: {}", error);
         }
+
+        Self { keys, css_provider }
     }
+}
 
-    today
+/// カードの行です。画面に表示しないIDや日時を、保存するときのために持っておきます。
+#[derive(Debug, Default)]
+pub struct CardRowExt {
+    card: cell::RefCell<Option<Card>>,
+    keys: cell::RefCell<Option<rc::Rc<Keys>>>,
 }
 
-fn weekday_to_japanese(weekday: chrono::Weekday) -> String {
-    match weekday {
-        chrono::Weekday::Mon => "月",
-        chrono::Weekday::Tue => "火",
-        chrono::Weekday::Wed => "水",
-        chrono::Weekday::Thu => "木",
-        chrono::Weekday::Fri => "金",
-        chrono::Weekday::Sat => "土",
-        chrono::Weekday::Sun => "日",
+#[glib::object_subclass]
+impl ObjectSubclass for CardRowExt {
+    const NAME: &'static str = "AssortedCardRow";
+    type Type = CardRow;
+    type ParentType = gtk::Box;
+}
+
+impl BoxImpl for CardRowExt {}
+impl ContainerImpl for CardRowExt {}
+impl WidgetImpl for CardRowExt {}
+impl ObjectImpl for CardRowExt {}
+
+glib::wrapper! {
+    pub struct CardRow(ObjectSubclass<CardRowExt>)
+        @extends gtk::Widget, gtk::Container, gtk::Box, @implements gtk::Orientable;
+}
+
+impl CardRow {
+    fn new(card: Card, keys: rc::Rc<Keys>) -> Self {
+        let row: Self = glib::Object::new(&[
+            ("name", &WIDGET_NAME_CARD),
+            ("orientation", &gtk::Orientation::Horizontal),
+            ("expand", &true),
+        ])
+        .unwrap_or_else(|_| panic!("{}", tr("カードの行を作れません。")));
+        row.get_ext().card.replace(Some(card));
+        row.get_ext().keys.replace(Some(keys));
+        row
+    }
+
+    fn get_ext(&self) -> &CardRowExt {
+        CardRowExt::from_instance(self)
+    }
+
+    fn card_id(&self) -> uuid::Uuid {
+        self.get_ext().card.borrow().as_ref().unwrap().id
+    }
+
+    fn keys(&self) -> rc::Rc<Keys> {
+        self.get_ext().keys.borrow().clone().unwrap()
+    }
+
+    /// 画面のキーと本文を反映したカードを返します。
+    fn read_card(&self) -> Card {
+        let key = find_card_key(self.upcast_ref())
+            .unwrap()
+            .active_id()
+            .unwrap();
+        let text = read_all(&find_card_text(self.upcast_ref()).unwrap());
+        let mut card = self.get_ext().card.borrow_mut();
+        let card = card.as_mut().unwrap();
+        card.update(&key, &text, &self.keys().keys);
+        card.clone()
     }
-    .to_string()
 }
 
-fn find_card(widget: &gtk::Widget) -> Option<gtk::Box> {
-    coo::libs::find_first_child_by_name(widget, WIDGET_NAME_CARD)
+fn find_card(widget: &gtk::Widget) -> Option<CardRow> {
+    crate::libs::find_first_child_by_name(widget, WIDGET_NAME_CARD)
 }
 
 fn find_card_text(widget: &gtk::Widget) -> Option<gtk::TextView> {
-    coo::libs::find_first_child_by_name(widget, WIDGET_NAME_CARD_TEXT)
+    crate::libs::find_first_child_by_name(widget, WIDGET_NAME_CARD_TEXT)
 }
 
 fn find_card_key(widget: &gtk::Widget) -> Option<gtk::ComboBoxText> {
-    coo::libs::find_first_child_by_name(widget, WIDGET_NAME_CARD_KEY)
+    crate::libs::find_first_child_by_name(widget, WIDGET_NAME_CARD_KEY)
 }
 
-fn build_text_view(text: &str, save: std::sync::Arc<Save>) -> gtk::TextView {
+fn build_text_view(text: &str, save: rc::Rc<Save>) -> gtk::TextView {
     let text_view = gtk::TextViewBuilder::new()
         .name(WIDGET_NAME_CARD_TEXT)
         .hexpand(true)
@@ -93,58 +304,40 @@ fn build_text_view(text: &str, save: std::sync::Arc<Save>) -> gtk::TextView {
     text_view.style_context().add_class("card-text");
     let buffer = text_view.buffer().unwrap();
     buffer.set_text(text);
-
-    let last = rc::Rc::new(cell::Cell::new(chrono::Utc::now()));
-    let dration_in_seconds = 1;
-    let auto_save_skip_duration = chrono::Duration::seconds(dration_in_seconds as i64);
-    let sleep_duration = std::time::Duration::from_secs(dration_in_seconds);
-    buffer.connect_changed(move |_buffer| {
-        let now = chrono::Utc::now();
-        last.replace(now);
-        let last = last.clone();
-        let save = save.clone();
-        glib::MainContext::default().spawn_local(async move {
-            async_std::task::sleep(sleep_duration).await;
-            if last.get() + auto_save_skip_duration > chrono::Utc::now() {
-                log::debug!("最終入力から十分に時間が経過していないので、保存処理を省略します。");
-                return;
-            }
-            save();
-        });
-    });
+    buffer.connect_changed(move |_buffer| save());
 
     text_view
 }
 
+/// カードを変更したことを伝えます。保存は`View::schedule_save`で、入力が止まるまで待ってからします。
 type Save = Box<dyn Fn()>;
-type SaveFactory = Box<dyn Fn(chrono::NaiveDate, &gtk::ListBox) -> Save>;
-fn save_column_factory_factory(root: &str) -> SaveFactory {
-    let root = root.to_string();
-    Box::new(
-        move |date: chrono::NaiveDate, list_box: &gtk::ListBox| -> Save {
-            let dir = coo::libs::expand_path(&format!("{}/{}", &root, &date.format("%Y/%Y-%m")));
-            let dest = format!("{}/{}.toml", &dir, &date.format("%Y-%m-%d"));
-            fs::create_dir_all(&dir).unwrap();
-            let list_box = list_box.clone();
-
-            Box::new(move || {
-                let mut cards: Vec<Card> = vec![];
-                for child in list_box.children() {
-                    let key = CARD_KEYS
-                        .get(find_card_key(&child).unwrap().active().unwrap() as usize)
-                        .unwrap()
-                        .to_string();
-                    cards.push(Card::new(key, read_all(&find_card_text(&child).unwrap())));
-                }
+type SaveFactory = Box<dyn Fn(BucketKey, &gtk::ListBox) -> Save>;
+fn save_column_factory_factory(view: &View) -> SaveFactory {
+    let view = view.downgrade();
+    Box::new(move |key: BucketKey, list_box: &gtk::ListBox| -> Save {
+        let view = view.clone();
+        let list_box = list_box.clone();
+
+        Box::new(move || {
+            if let Some(view) = view.upgrade() {
+                let list_box = list_box.clone();
+                view.schedule_save(
+                    key,
+                    Box::new(glib::clone!(@weak view => move || {
+                        view.save_edit(key, read_cards(&list_box));
+                    })),
+                );
+            }
+        })
+    })
+}
 
-                let content = toml::to_string_pretty(&DailyBucket::new(date, cards)).unwrap();
-                log::debug!("保存先: {}, 保存内容:\n{}", &dest, &content);
-                let mut file = fs::File::create(&dest).unwrap();
-                file.write_all(content.as_bytes()).unwrap();
-                file.flush().unwrap();
-            })
-        },
-    )
+fn read_cards(list_box: &gtk::ListBox) -> Vec<Card> {
+    list_box
+        .children()
+        .iter()
+        .map(|child| find_card(child).unwrap().read_card())
+        .collect()
 }
 
 fn read_all(text_view: &gtk::TextView) -> String {
@@ -157,65 +350,127 @@ fn read_all_text_buffer(text_buffer: &gtk::TextBuffer) -> String {
     text_buffer.text(&start, &end, false).unwrap().to_string()
 }
 
-fn build_row(card: Option<Card>, save: sync::Arc<Save>) -> gtk::Box {
-    let hbox = gtk::BoxBuilder::new()
-        .name(WIDGET_NAME_CARD)
-        .orientation(gtk::Orientation::Horizontal)
-        .expand(true)
+/// テーマでキーごとに色を付けられるように、カードにキーのクラスを付けます。
+fn set_key_class(card: &CardRow, key: &str) {
+    let style_context = card.style_context();
+    for class in style_context.list_classes() {
+        if class.starts_with("card-key-") {
+            style_context.remove_class(&class);
+        }
+    }
+    style_context.add_class(&theme::key_class(key));
+}
+
+/// キーを選ぶときに表示する説明です。
+fn key_tooltip(keys: &card::CardKeys, symbol: &str) -> Option<String> {
+    match keys.find(symbol) {
+        Some(key) => key.label.clone(),
+        None => Some(tr("設定に無いキーです。").to_string()),
+    }
+}
+
+fn build_row(card: Option<Card>, keys: rc::Rc<Keys>, save: rc::Rc<Save>) -> CardRow {
+    let card = card
+        .unwrap_or_else(|| Card::new(keys.keys.first().symbol.clone(), String::new(), &keys.keys));
+    let hbox = CardRow::new(card.clone(), keys.clone());
+    hbox.style_context().add_class("card");
+    hbox.style_context()
+        .add_provider(&keys.css_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+
+    // TextViewの上ではドラッグが文字の選択になるので、つまみを付けます。
+    let handle = gtk::EventBoxBuilder::new()
+        .tooltip_text(tr("ドラッグして並べ替える"))
         .build();
+    handle.add(&gtk::Label::new(Some("⠿")));
+    handle.style_context().add_class("card-handle");
+    handle.drag_source_set(
+        gdk::ModifierType::BUTTON1_MASK,
+        &drag_targets(),
+        gdk::DragAction::MOVE,
+    );
+    handle.connect_drag_data_get(
+        glib::clone!(@weak hbox => move |_, _, selection_data, _, _| {
+            selection_data.set_text(&hbox.card_id().to_string());
+        }),
+    );
+    hbox.add(&handle);
 
     let combo_box_text = gtk::ComboBoxTextBuilder::new()
         .name(WIDGET_NAME_CARD_KEY)
         .build();
     combo_box_text.style_context().add_class("card-key");
-    for key in CARD_KEYS {
-        combo_box_text.append_text(key);
+    for key in keys.keys.iter() {
+        combo_box_text.append(Some(&key.symbol), &key.symbol);
+    }
+    // 設定に無いキーも、書き換えてしまわないように選べるようにしておきます。
+    if keys.keys.find(&card.key).is_none() {
+        combo_box_text.append(Some(&card.key), &card.key);
     }
-    let index = match card {
-        Some(ref card) => CARD_KEYS
-            .iter()
-            .position(|&key| key == card.key)
-            .unwrap_or(0) as u32,
-        None => 0,
-    };
 
-    combo_box_text.set_active(Some(index));
+    combo_box_text.set_active_id(Some(&card.key));
+    combo_box_text.set_tooltip_text(key_tooltip(&keys.keys, &card.key).as_deref());
+    set_key_class(&hbox, &card.key);
+    {
+        let save = save.clone();
+        combo_box_text.connect_changed(glib::clone!(@weak hbox => move |combo_box_text| {
+            if let Some(key) = combo_box_text.active_id() {
+                set_key_class(&hbox, &key);
+                combo_box_text.set_tooltip_text(key_tooltip(&hbox.keys().keys, &key).as_deref());
+            }
+            // 作業を終えた日時を記録するために、ここでカードに反映します。
+            hbox.read_card();
+            save();
+        }));
+    }
     hbox.add(&combo_box_text);
-
-    let text = match card {
-        Some(ref card) => &card.text,
-        _ => "",
-    };
-    hbox.add(&build_text_view(text, save));
+    if let (Some(days), Some(carried_from)) = (
+        card.pending_days(chrono::Local::today().naive_local()),
+        &card.carried_from,
+    ) {
+        let label = gtk::LabelBuilder::new()
+            .label(&i18n::format("{}日", &[&days]))
+            .tooltip_text(&i18n::format(
+                "{}から持ち越しています。",
+                &[&i18n::format_month_day(carried_from.date)],
+            ))
+            .build();
+        label.style_context().add_class("card-pending");
+        hbox.add(&label);
+    }
+    hbox.add(&build_text_view(&card.text, save));
 
     hbox
 }
 
-fn delete_empty_rows_except_last(list_box: &gtk::ListBox) {
+/// 最後の行以外の空の行を消します。消したかを返します。
+fn delete_empty_rows_except_last(list_box: &gtk::ListBox) -> bool {
     if let Some((_, sub_children)) = list_box.children().split_last() {
         for child in sub_children {
             let text_view = find_card_text(child).unwrap();
             if read_all(&text_view).is_empty() {
                 list_box.remove(child);
-                break;
+                return true;
             }
         }
     }
+
+    false
 }
 
-fn add_row_if_last_is_not_empty(list_box: &gtk::ListBox, save: sync::Arc<Save>) {
+fn add_row_if_last_is_not_empty(list_box: &gtk::ListBox, save: rc::Rc<Save>) {
     let children = list_box.children();
-    let text_view = find_card_text(children.last().unwrap()).unwrap();
+    let last = children.last().unwrap();
+    let text_view = find_card_text(last).unwrap();
     if !read_all(&text_view).is_empty() {
-        let row = build_row(None, save.clone());
+        let keys = find_card(last).unwrap().keys();
+        let row = build_row(None, keys, save.clone());
         list_box.add(&row);
         list_box.show_all();
     }
 }
 
-fn on_row_added_to_list_box_factory(
-    save: sync::Arc<Save>,
-) -> Box<dyn Fn(&gtk::ListBox, &gtk::Widget)> {
+type OnRowAdded = Box<dyn Fn(&gtk::ListBox, &gtk::Widget)>;
+fn on_row_added_to_list_box_factory(save: rc::Rc<Save>) -> OnRowAdded {
     Box::new(move |list_box: &gtk::ListBox, row: &gtk::Widget| {
         // ListBoxRowをフォーカス不可にしないと、ListBoxにaddしたTextViewが選択後即座にフォーカスを失います。
         for child in list_box.children() {
@@ -229,9 +484,12 @@ fn on_row_added_to_list_box_factory(
         let text_view = find_card_text(row).unwrap();
         {
             let list_box = list_box.clone();
+            let save = save.clone();
             text_view.connect_focus_out_event(move |_, _| {
                 log::debug!("TextViewがフォーカスを失ったイベントのシグナル");
-                delete_empty_rows_except_last(&list_box);
+                if delete_empty_rows_except_last(&list_box) {
+                    save();
+                }
                 gtk::Inhibit(false)
             });
         }
@@ -247,43 +505,119 @@ fn on_row_added_to_list_box_factory(
     })
 }
 
-fn build_column(daily_bucket: DailyBucket, save_factory: sync::Arc<SaveFactory>) -> gtk::Box {
+/// 日か、週の見出しの下に置く週のメモを作ります。どちらも同じように書けて、同じように保存します。
+fn build_column(
+    key: BucketKey,
+    cards: Vec<Card>,
+    keys: rc::Rc<Keys>,
+    save_factory: rc::Rc<SaveFactory>,
+) -> (gtk::Box, Column) {
     let vbox = gtk::BoxBuilder::new()
         .orientation(gtk::Orientation::Vertical)
-        .expand(true)
         .build();
+    let title = match key {
+        BucketKey::Day(date) => {
+            vbox.set_expand(true);
+            vbox.style_context().add_class("day-column");
+            gtk::Label::new(Some(&i18n::format(
+                "{}日 ({})",
+                &[&date.day(), &i18n::weekday_name(date.weekday())],
+            )))
+        }
+        BucketKey::Week(_) => {
+            vbox.style_context().add_class("week-notes");
+            gtk::LabelBuilder::new()
+                .label(tr("今週のメモ"))
+                .xalign(0.0)
+                .build()
+        }
+    };
+    vbox.add(&title);
 
-    let title = format!(
-        "{}日 ({})",
-        daily_bucket.date.day(),
-        weekday_to_japanese(daily_bucket.date.weekday())
-    );
-    vbox.add(&gtk::Label::new(Some(&title)));
+    let info_bar = gtk::InfoBarBuilder::new()
+        .message_type(gtk::MessageType::Error)
+        .no_show_all(true)
+        .build();
+    vbox.add(&info_bar);
 
     let list_box = gtk::ListBoxBuilder::new()
-        .expand(true)
         .selection_mode(gtk::SelectionMode::None)
         .build();
 
-    let save = sync::Arc::new(save_factory(daily_bucket.date, &list_box));
+    let save = rc::Rc::new(save_factory(key, &list_box));
     // すべてのListBoxRowにフォーカス不可を設定するために、最初の要素をListBoxにaddする前に、このconnectをしなければなりません。
     list_box.connect_add(on_row_added_to_list_box_factory(save.clone()));
 
-    for card in daily_bucket.cards {
-        if !card.text.is_empty() {
-            list_box.add(&build_row(Some(card), save.clone()));
-        }
+    fill_list_box(&list_box, cards, keys, save);
+
+    // 週のメモは見出しと一緒にスクロールするので、日だけをスクロールできるようにします。
+    if let BucketKey::Day(_) = key {
+        list_box.set_expand(true);
+        let scrolled_window = gtk::ScrolledWindowBuilder::new().build();
+        scrolled_window.add(&list_box);
+        vbox.add(&scrolled_window);
+    } else {
+        vbox.add(&list_box);
     }
-    list_box.add(&build_row(None, save.clone()));
 
-    let scrolled_window = gtk::ScrolledWindowBuilder::new().build();
-    scrolled_window.add(&list_box);
-    vbox.add(&scrolled_window);
+    let column = Column {
+        key,
+        list_box,
+        info_bar,
+    };
+    (vbox, column)
+}
 
-    vbox
+/// カードと、末尾に新しいカードを書くための空のカードを並べます。
+fn fill_list_box(
+    list_box: &gtk::ListBox,
+    cards: Vec<Card>,
+    keys: rc::Rc<Keys>,
+    save: rc::Rc<Save>,
+) {
+    for card in cards {
+        if !card.text.is_empty() {
+            list_box.add(&build_row(Some(card), keys.clone(), save.clone()));
+        }
+    }
+    list_box.add(&build_row(None, keys, save));
 }
 
-static CARD_KEYS: &[&str] = &["📝", "🔲", "✅", "📅"];
+/// 日の上に、読み込みや保存の失敗などを表示します。`buttons`で、どうするかを選べるようにします。
+fn show_column_message(
+    info_bar: &gtk::InfoBar,
+    message_type: gtk::MessageType,
+    message: &str,
+    buttons: &[gtk::Button],
+) {
+    info_bar.set_message_type(message_type);
+    let content_area = info_bar.content_area();
+    for child in content_area.children() {
+        content_area.remove(&child);
+    }
+    let vbox = gtk::BoxBuilder::new()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(4)
+        .build();
+    vbox.add(
+        &gtk::LabelBuilder::new()
+            .label(message)
+            .wrap(true)
+            .xalign(0.0)
+            .build(),
+    );
+    let hbox = gtk::BoxBuilder::new()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(4)
+        .build();
+    for button in buttons {
+        hbox.add(button);
+    }
+    vbox.add(&hbox);
+    content_area.add(&vbox);
+    vbox.show_all();
+    info_bar.show();
+}
 
 #[glib::object_subclass]
 impl ObjectSubclass for ViewExt {
@@ -295,13 +629,32 @@ impl ObjectSubclass for ViewExt {
         let grid = gtk::Grid::new();
         grid.set_column_homogeneous(true);
         grid.set_row_homogeneous(true);
-        grid.set_column_spacing(4);
-        grid.set_row_spacing(4);
 
         Self {
             date: cell::RefCell::new(chrono::Local::today().naive_local()),
             widget: cell::RefCell::new(grid),
             path: cell::RefCell::new(".".to_string()),
+            config: cell::RefCell::new(Config::default()),
+            keys: cell::RefCell::new(rc::Rc::new(Keys::new(card::CardKeys::default()))),
+            month_mode: cell::Cell::new(false),
+            last_carry_over: cell::Cell::new(None),
+            pending_saves: cell::RefCell::new(vec![]),
+            save_generation: cell::Cell::new(0),
+            undo_stack: cell::RefCell::new(vec![]),
+            redo_stack: cell::RefCell::new(vec![]),
+            toast: gtk::InfoBarBuilder::new()
+                .message_type(gtk::MessageType::Info)
+                .show_close_button(true)
+                .no_show_all(true)
+                .build(),
+            toast_generation: cell::Cell::new(0),
+            columns: cell::RefCell::new(vec![]),
+            unsaved: cell::RefCell::new(collections::BTreeMap::new()),
+            known: cell::RefCell::new(collections::BTreeMap::new()),
+            monitors: cell::RefCell::new(vec![]),
+            search: Search::new(),
+            index: cell::RefCell::new(card::CardIndex::default()),
+            search_hits: cell::RefCell::new(vec![]),
         }
     }
 }
@@ -314,7 +667,15 @@ impl ObjectImpl for ViewExt {
     fn constructed(&self, obj: &Self::Type) {
         self.parent_constructed(obj);
 
-        obj.add(&self.widget.borrow().clone());
+        let vbox = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+        vbox.add(&self.search.bar);
+        vbox.add(&self.search.scrolled_window);
+        vbox.add(&self.widget.borrow().clone());
+        self.toast.connect_response(|toast, _| toast.hide());
+        vbox.add(&self.toast);
+        obj.add(&vbox);
     }
 
     fn properties() -> &'static [glib::ParamSpec] {
@@ -347,84 +708,435 @@ impl ObjectImpl for ViewExt {
 
 glib::wrapper! {
     pub struct View(ObjectSubclass<ViewExt>)
-        @extends gtk::Widget, gtk::Container, gtk::Bin, gtk::Window, gtk::ApplicationWindow;
+        @extends gtk::Widget, gtk::Container, gtk::Bin;
 }
 
 impl View {
-    fn load_daily_bucket(&self, date: chrono::NaiveDate) -> DailyBucket {
+    fn load_cards(&self, key: BucketKey) -> Result<Vec<Card>, card::StorageError> {
+        key.load(&self.get_ext().path.borrow())
+    }
+
+    fn find_info_bar(&self, key: BucketKey) -> Option<gtk::InfoBar> {
+        self.get_ext()
+            .columns
+            .borrow()
+            .iter()
+            .find(|column| column.key == key)
+            .map(|column| column.info_bar.clone())
+    }
+
+    /// `key`のカードを保存します。保存できなければ、再試行できるようにカードを保持しておきます。保存できたかを返します。
+    fn save_cards(&self, key: BucketKey, cards: Vec<Card>) -> bool {
+        let ext = self.get_ext();
+        // 前に読み込んだときから外部で変更されていれば、上書きせずにどうするかを選んでもらいます。
+        let known = ext.known.borrow().get(&key).cloned();
+        if let (Some(known), Ok(theirs)) = (known, self.load_cards(key)) {
+            if !card::same_cards(&known, &theirs) {
+                ext.unsaved.borrow_mut().insert(key, cards);
+                self.show_conflict(key, theirs);
+                return false;
+            }
+        }
+
+        let root = ext.path.borrow().clone();
+        let result = key.save(&root, cards.clone(), ext.config.borrow().backups);
+        let info_bar = self.find_info_bar(key);
+        match result {
+            Ok(()) => {
+                self.get_ext().unsaved.borrow_mut().remove(&key);
+                self.get_ext().known.borrow_mut().insert(key, cards);
+                if let Some(info_bar) = info_bar {
+                    info_bar.hide();
+                }
+                true
+            }
+            Err(error) => {
+                log::error!("保存できませんでした: {}", error);
+                self.get_ext().unsaved.borrow_mut().insert(key, cards);
+                if let Some(info_bar) = info_bar {
+                    self.show_unsaved(
+                        &info_bar,
+                        key,
+                        &i18n::format(
+                            "保存できませんでした。入力した内容は保持しているので、再試行できます。\n{}",
+                            &[&error],
+                        ),
+                    );
+                }
+                false
+            }
+        }
+    }
+
+    /// 変更を保存する`save`を、入力が止まるまで待ってから呼びます。同じ保存先の前の変更は、この変更にまとめます。
+    fn schedule_save(&self, key: BucketKey, save: Save) {
         let ext = self.get_ext();
+        {
+            let mut pending_saves = ext.pending_saves.borrow_mut();
+            pending_saves.retain(|pending| pending.key != key);
+            pending_saves.push(PendingSave { key, save });
+        }
 
-        let mut daily_bucket = DailyBucket {
-            version: 1,
-            date,
-            cards: vec![],
+        let generation = ext.save_generation.get() + 1;
+        ext.save_generation.set(generation);
+        glib::timeout_add_local(
+            SAVE_DELAY,
+            glib::clone!(@weak self as this => @default-return glib::Continue(false), move || {
+                if this.get_ext().save_generation.get() == generation {
+                    this.flush_saves();
+                } else {
+                    log::debug!("最終入力から十分に時間が経過していないので、保存処理を省略します。");
+                }
+                glib::Continue(false)
+            }),
+        );
+    }
+
+    /// 保存を待っている変更を、すぐに保存します。
+    pub fn flush_saves(&self) {
+        let pending_saves = self.get_ext().pending_saves.take();
+        for pending in pending_saves {
+            (pending.save)();
+        }
+    }
+
+    /// 画面で編集したカードを保存し、元に戻せるように記録します。カードを消していれば、元に戻せることを知らせます。
+    fn save_edit(&self, key: BucketKey, cards: Vec<Card>) {
+        let before = self.get_ext().known.borrow().get(&key).cloned();
+        if !self.save_cards(key, cards.clone()) {
+            return;
+        }
+        let before = match before {
+            Some(before) if !card::same_cards(&before, &cards) => before,
+            _ => return,
         };
-        let dir = coo::libs::expand_path(&format!(
-            "{}/{}",
-            ext.path.borrow(),
-            &date.format("%Y/%Y-%m")
-        ));
-        let source = format!("{}/{}.toml", &dir, &date.format("%Y-%m-%d"));
-        match fs::read_to_string(source) {
-            Ok(file) => {
-                let v = file.parse::<toml::Value>().unwrap();
-                daily_bucket.cards = v["cards"]
-                    .as_array()
-                    .unwrap()
+        let removed = before
+            .iter()
+            .filter(|card| !card.text.is_empty())
+            .filter(|card| {
+                !cards
                     .iter()
-                    .map(|source| Card {
-                        key: source["key"].as_str().unwrap().to_string(),
-                        text: source["text"].as_str().unwrap().to_string(),
-                    })
-                    .collect();
-                daily_bucket
+                    .any(|after| after.id == card.id && !after.text.is_empty())
+            })
+            .count();
+        self.push_undo(vec![Change {
+            key,
+            before,
+            after: cards,
+        }]);
+        if removed > 0 {
+            self.show_undo_toast(&i18n::format("{}件のカードを削除しました。", &[&removed]));
+        }
+    }
+
+    fn push_undo(&self, step: Vec<Change>) {
+        let mut undo_stack = self.get_ext().undo_stack.borrow_mut();
+        undo_stack.push(step);
+        if undo_stack.len() > UNDO_LIMIT {
+            undo_stack.remove(0);
+        }
+        self.get_ext().redo_stack.borrow_mut().clear();
+    }
+
+    /// 変更の前か後のカードに戻して保存します。表示していないファイルなら、その週を表示します。
+    /// すべて保存できたかを返します。
+    fn apply_changes(&self, step: &[Change], before: bool) -> bool {
+        if let Some(change) = step.first() {
+            let visible = self
+                .get_ext()
+                .columns
+                .borrow()
+                .iter()
+                .any(|column| column.key == change.key);
+            if !visible {
+                self.show_week(change.key.date());
+            }
+        }
+        let mut saved = true;
+        for change in step {
+            let cards = if before {
+                change.before.clone()
+            } else {
+                change.after.clone()
+            };
+            self.replace_cards(change.key, cards.clone());
+            saved &= self.save_cards(change.key, cards);
+        }
+
+        saved
+    }
+
+    /// 保存できなければ、競合や失敗を解決してからもう一度できるように、元に戻す変更をそのまま残します。
+    fn undo(&self) {
+        self.flush_saves();
+        let step = self.get_ext().undo_stack.borrow_mut().pop();
+        if let Some(step) = step {
+            if self.apply_changes(&step, true) {
+                self.get_ext().redo_stack.borrow_mut().push(step);
+                self.get_ext().toast.hide();
+            } else {
+                self.get_ext().undo_stack.borrow_mut().push(step);
+            }
+        }
+    }
+
+    fn redo(&self) {
+        self.flush_saves();
+        let step = self.get_ext().redo_stack.borrow_mut().pop();
+        if let Some(step) = step {
+            if self.apply_changes(&step, false) {
+                self.get_ext().undo_stack.borrow_mut().push(step);
+            } else {
+                self.get_ext().redo_stack.borrow_mut().push(step);
+            }
+        }
+    }
+
+    /// 元に戻すボタンを持ったトーストを、しばらく表示します。
+    fn show_undo_toast(&self, message: &str) {
+        let ext = self.get_ext();
+        let undo_button = gtk::Button::with_label(tr("元に戻す"));
+        undo_button.connect_clicked(glib::clone!(@weak self as this => move |_| {
+            this.undo();
+        }));
+        show_column_message(&ext.toast, gtk::MessageType::Info, message, &[undo_button]);
+
+        let generation = ext.toast_generation.get() + 1;
+        ext.toast_generation.set(generation);
+        glib::timeout_add_seconds_local(
+            8,
+            glib::clone!(@weak self as this => @default-return glib::Continue(false), move || {
+                if this.get_ext().toast_generation.get() == generation {
+                    this.get_ext().toast.hide();
+                }
+                glib::Continue(false)
+            }),
+        );
+    }
+
+    fn column_cards(&self, key: BucketKey) -> Option<Vec<Card>> {
+        self.get_ext()
+            .columns
+            .borrow()
+            .iter()
+            .find(|column| column.key == key)
+            .map(|column| read_cards(&column.list_box))
+    }
+
+    /// `key`のファイルとして表示しているカードを`cards`で置き換えます。
+    fn replace_cards(&self, key: BucketKey, cards: Vec<Card>) {
+        let columns = self.get_ext().columns.borrow();
+        if let Some(column) = columns.iter().find(|column| column.key == key) {
+            let save = rc::Rc::new(save_column_factory_factory(self)(key, &column.list_box));
+            for child in column.list_box.children() {
+                column.list_box.remove(&child);
+            }
+            fill_list_box(
+                &column.list_box,
+                cards,
+                self.get_ext().keys.borrow().clone(),
+                save,
+            );
+            column.list_box.set_sensitive(true);
+            column.list_box.show_all();
+            column.info_bar.hide();
+        }
+    }
+
+    /// 表示中の日と週のメモのファイルを監視します。
+    fn watch_files(&self) {
+        let ext = self.get_ext();
+        let root = ext.path.borrow().clone();
+        let monitors = ext
+            .columns
+            .borrow()
+            .iter()
+            .filter_map(|column| {
+                let key = column.key;
+                let file = gio::File::for_path(key.file_path(&root));
+                let monitor = file
+                    .monitor_file(gio::FileMonitorFlags::NONE, gio::NONE_CANCELLABLE)
+                    .map_err(|error| log::warn!("ファイルの変更を監視できません: {}", error))
+                    .ok()?;
+                monitor.connect_changed(glib::clone!(@weak self as this => move |_, _, _, event| {
+                    if matches!(
+                        event,
+                        gio::FileMonitorEvent::ChangesDoneHint
+                            | gio::FileMonitorEvent::Created
+                            | gio::FileMonitorEvent::Deleted
+                    ) {
+                        this.on_file_changed(key);
+                    }
+                }));
+                Some(monitor)
+            })
+            .collect::<Vec<gio::FileMonitor>>();
+        for monitor in ext.monitors.replace(monitors) {
+            monitor.cancel();
+        }
+    }
+
+    /// `key`のファイルが変更されたときに呼ばれます。
+    /// 自分で保存したのではない変更なら、手元に変更が無ければ読み込み直し、あればどうするかを選んでもらいます。
+    fn on_file_changed(&self, key: BucketKey) {
+        let theirs = match self.load_cards(key) {
+            Ok(cards) => cards,
+            Err(error) => {
+                log::warn!("変更されたファイルを読み込めません: {}", error);
+                return;
+            }
+        };
+        let known = self.get_ext().known.borrow().get(&key).cloned();
+        if let Some(ref known) = known {
+            if card::same_cards(known, &theirs) {
+                return;
             }
-            _ => daily_bucket,
+        }
+        let mine = match self.column_cards(key) {
+            Some(mine) => mine,
+            None => return,
+        };
+        // 読み込めていなかった日は編集できないので、手元の変更はありません。
+        let changed_locally = match known {
+            Some(ref known) => !card::same_cards(known, &mine),
+            None => false,
+        };
+        if changed_locally {
+            self.show_conflict(key, theirs);
+        } else {
+            log::debug!("外部で変更されたので読み込み直します: {:?}", key);
+            self.get_ext()
+                .known
+                .borrow_mut()
+                .insert(key, theirs.clone());
+            self.replace_cards(key, theirs);
         }
     }
 
+    /// 手元と外部の両方で`key`のカードが変更されたときに、どちらを使うか、統合するかを選んでもらいます。
+    fn show_conflict(&self, key: BucketKey, theirs: Vec<Card>) {
+        let info_bar = match self.find_info_bar(key) {
+            Some(info_bar) => info_bar,
+            None => return,
+        };
+        let theirs = rc::Rc::new(theirs);
+
+        let keep_mine_button = gtk::Button::with_label(tr("自分の変更を残す"));
+        keep_mine_button.connect_clicked(
+            glib::clone!(@weak self as this, @strong theirs => move |_| {
+                this.get_ext().known.borrow_mut().insert(key, theirs.to_vec());
+                if let Some(mine) = this.column_cards(key) {
+                    this.save_cards(key, mine);
+                }
+            }),
+        );
+
+        let take_theirs_button = gtk::Button::with_label(tr("外部の変更を使う"));
+        take_theirs_button.connect_clicked(
+            glib::clone!(@weak self as this, @strong theirs => move |_| {
+                this.get_ext().unsaved.borrow_mut().remove(&key);
+                this.get_ext().known.borrow_mut().insert(key, theirs.to_vec());
+                this.replace_cards(key, theirs.to_vec());
+            }),
+        );
+
+        let merge_button = gtk::Button::with_label(tr("統合する"));
+        merge_button.connect_clicked(
+            glib::clone!(@weak self as this, @strong theirs => move |_| {
+                if let Some(mine) = this.column_cards(key) {
+                    let merged = card::merge_cards(&theirs, &mine);
+                    this.get_ext().known.borrow_mut().insert(key, theirs.to_vec());
+                    this.replace_cards(key, merged.clone());
+                    this.save_cards(key, merged);
+                }
+            }),
+        );
+
+        show_column_message(
+            &info_bar,
+            gtk::MessageType::Warning,
+            tr("このファイルは外部でも変更されました。どちらの変更を使うか選んでください。"),
+            &[keep_mine_button, take_theirs_button, merge_button],
+        );
+    }
+
+    fn show_unsaved(&self, info_bar: &gtk::InfoBar, key: BucketKey, message: &str) {
+        let retry_button = gtk::Button::with_label(tr("再試行"));
+        retry_button.connect_clicked(glib::clone!(@weak self as this => move |_| {
+            let cards = this.get_ext().unsaved.borrow().get(&key).cloned();
+            if let Some(cards) = cards {
+                this.save_cards(key, cards);
+            }
+        }));
+        show_column_message(info_bar, gtk::MessageType::Error, message, &[retry_button]);
+    }
+
     fn build_week_column(&self) -> gtk::Box {
         let view = self.clone();
 
         let next_button = gtk::ButtonBuilder::new().label("▶").build();
         {
             next_button.connect_clicked(gtk::glib::clone!(@weak view => move |_| {
-                let ext = view.get_ext();
-                let new_date = *ext.date.borrow() + chrono::Duration::days(7);
-                ext.date.replace(new_date);
-                view.reload_root_grid();
+                view.move_period(1);
             }));
         }
 
         let previous_button = gtk::ButtonBuilder::new().label("◀").build();
         {
             previous_button.connect_clicked(gtk::glib::clone!(@weak view => move |_| {
-                let ext = view.get_ext();
-                let new_date = *ext.date.borrow() - chrono::Duration::days(7);
-                ext.date.replace(new_date);
-                view.reload_root_grid();
+                view.move_period(-1);
             }));
         }
 
-        let ext = self.get_ext();
-        let date = ext.date.borrow();
-        let iso_week = date.iso_week();
-        let title = format!("{}年第{}週", iso_week.year(), iso_week.week());
-        let start_of_week = compute_last_monday(*date);
-        let end_of_week = start_of_week + chrono::Duration::days(6);
-        let subtitle = format!(
-            "{} ~ {}",
-            start_of_week.format("%-m月%-d日"),
-            end_of_week.format("%-m月%-d日")
-        );
+        let date = self.date();
+        let (title, subtitle) = if self.get_ext().month_mode.get() {
+            (date.format(tr("%Y年%-m月")).to_string(), String::new())
+        } else {
+            let config = self.get_ext().config.borrow().clone();
+            let iso_week = config.week_of(date);
+            let title = i18n::format("{}年第{}週", &[&iso_week.year(), &iso_week.week()]);
+            let first_date = config.first_date(date);
+            let last_date = first_date + chrono::Duration::days(config.days() as i64 - 1);
+            let subtitle = format!(
+                "{} ~ {}",
+                i18n::format_month_day(first_date),
+                i18n::format_month_day(last_date)
+            );
+            (title, subtitle)
+        };
         let header = gtk::HeaderBarBuilder::new()
-            .title(&title)
-            .subtitle(&subtitle)
+            .custom_title(&self.build_date_picker(&title, &subtitle))
             .expand(false)
             .build();
         header.pack_start(&previous_button);
         header.pack_end(&next_button);
 
+        let today_button = gtk::ButtonBuilder::new().label(tr("今日")).build();
+        today_button.connect_clicked(gtk::glib::clone!(@weak view => move |_| {
+            view.set_date(chrono::Local::today().naive_local());
+        }));
+        header.pack_start(&today_button);
+
+        let month_button = gtk::ToggleButtonBuilder::new()
+            .label(tr("月"))
+            .tooltip_text(tr("月の一覧を表示する"))
+            .active(self.get_ext().month_mode.get())
+            .build();
+        month_button.connect_toggled(gtk::glib::clone!(@weak view => move |button| {
+            view.get_ext().month_mode.set(button.is_active());
+            view.reload_root_grid();
+        }));
+        header.pack_end(&month_button);
+
+        let carry_over_button = gtk::ButtonBuilder::new()
+            .label("⤵")
+            .tooltip_text(tr("前の日の終わっていない作業を今日に持ち越す"))
+            .build();
+        carry_over_button.connect_clicked(gtk::glib::clone!(@weak view => move |_| {
+            view.carry_over();
+        }));
+        header.pack_end(&carry_over_button);
+
         let root = gtk::BoxBuilder::new()
             .orientation(gtk::Orientation::Vertical)
             .expand(true)
@@ -433,7 +1145,210 @@ impl View {
         root
     }
 
+    /// 週の見出しを、押すとカレンダーで日付を選べるボタンにします。
+    fn build_date_picker(&self, title: &str, subtitle: &str) -> gtk::MenuButton {
+        let vbox = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+        let title = gtk::Label::new(Some(title));
+        title.style_context().add_class("title");
+        vbox.add(&title);
+        let subtitle = gtk::Label::new(Some(subtitle));
+        subtitle.style_context().add_class("subtitle");
+        vbox.add(&subtitle);
+
+        let calendar = gtk::Calendar::new();
+        let popover = gtk::PopoverBuilder::new().build();
+        popover.add(&calendar);
+        calendar.show();
+        let menu_button = gtk::MenuButtonBuilder::new()
+            .relief(gtk::ReliefStyle::None)
+            .tooltip_text(tr("日付を選ぶ"))
+            .popover(&popover)
+            .build();
+        menu_button.add(&vbox);
+
+        // 月を移ると、続けて日も選ばれます。それで週を移らないように、月を移った直後の日の選択は無視します。
+        let month_changed = rc::Rc::new(cell::Cell::new(false));
+        popover.connect_show(
+            glib::clone!(@weak self as this, @weak calendar, @strong month_changed => move |_| {
+                let date = this.date();
+                calendar.select_month(date.month0(), date.year() as u32);
+                calendar.select_day(date.day());
+                month_changed.set(false);
+                this.mark_days_with_cards(&calendar);
+            }),
+        );
+        calendar.connect_month_changed(
+            glib::clone!(@weak self as this, @strong month_changed => move |calendar| {
+                month_changed.set(true);
+                this.mark_days_with_cards(calendar);
+            }),
+        );
+        calendar.connect_day_selected(
+            glib::clone!(@weak self as this, @weak popover, @strong month_changed => move |calendar| {
+                if month_changed.replace(false) {
+                    return;
+                }
+                let (year, month, day) = calendar.date();
+                if let Some(date) = chrono::NaiveDate::from_ymd_opt(year as i32, month + 1, day) {
+                    if date != this.date() {
+                        popover.hide();
+                        this.set_date(date);
+                    }
+                }
+            }),
+        );
+
+        menu_button
+    }
+
+    /// カレンダーに表示中の月のうち、カードのファイルがある日に印を付けます。
+    fn mark_days_with_cards(&self, calendar: &gtk::Calendar) {
+        calendar.clear_marks();
+        let (year, month, _) = calendar.date();
+        if let Some(date) = chrono::NaiveDate::from_ymd_opt(year as i32, month + 1, 1) {
+            for date in card::dates_with_cards(&self.get_ext().path.borrow(), date) {
+                calendar.mark_day(date.day());
+            }
+        }
+    }
+
+    /// 月の一覧で、`date`の日を表すボタンを作ります。キーごとのカードの数と、カードの最初の行を表示します。
+    fn build_month_day(&self, date: chrono::NaiveDate) -> gtk::Button {
+        let vbox = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+        let day = gtk::LabelBuilder::new()
+            .label(&date.day().to_string())
+            .xalign(0.0)
+            .build();
+        vbox.add(&day);
+
+        match self.load_cards(BucketKey::Day(date)) {
+            Ok(cards) => {
+                let cards = cards
+                    .into_iter()
+                    .filter(|card| !card.text.is_empty())
+                    .collect::<Vec<Card>>();
+                // 設定の順に数え、設定に無いキーはその後に並べます。
+                let mut counts: Vec<(String, usize)> = vec![];
+                for key in self.get_ext().keys.borrow().keys.iter() {
+                    counts.push((key.symbol.clone(), 0));
+                }
+                for card in &cards {
+                    match counts.iter_mut().find(|(key, _)| *key == card.key) {
+                        Some((_, count)) => *count += 1,
+                        None => counts.push((card.key.clone(), 1)),
+                    }
+                }
+                let counts = counts
+                    .iter()
+                    .filter(|(_, count)| *count > 0)
+                    .map(|(key, count)| format!("{}{}", key, count))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                let counts = gtk::LabelBuilder::new().label(&counts).xalign(0.0).build();
+                counts.style_context().add_class("month-day-counts");
+                vbox.add(&counts);
+
+                let lines = 3;
+                for card in cards.iter().take(lines) {
+                    let text = gtk::LabelBuilder::new()
+                        .label(card.text.lines().next().unwrap_or(""))
+                        .ellipsize(gtk::pango::EllipsizeMode::End)
+                        .xalign(0.0)
+                        .build();
+                    text.style_context().add_class("month-day-text");
+                    vbox.add(&text);
+                }
+                if cards.len() > lines {
+                    let more = gtk::LabelBuilder::new()
+                        .label(&i18n::format("ほか{}件", &[&(cards.len() - lines)]))
+                        .xalign(0.0)
+                        .build();
+                    more.style_context().add_class("month-day-text");
+                    vbox.add(&more);
+                }
+            }
+            Err(error) => {
+                let label = gtk::LabelBuilder::new()
+                    .label("⚠")
+                    .tooltip_text(&error.to_string())
+                    .xalign(0.0)
+                    .build();
+                vbox.add(&label);
+            }
+        }
+
+        let button = gtk::ButtonBuilder::new()
+            .relief(gtk::ReliefStyle::None)
+            .expand(true)
+            .build();
+        button.add(&vbox);
+        button.style_context().add_class("month-day");
+        if date == chrono::Local::today().naive_local() {
+            button.style_context().add_class("month-day-today");
+        }
+        button.connect_clicked(glib::clone!(@weak self as this => move |_| {
+            this.show_week(date);
+        }));
+
+        button
+    }
+
+    /// 表示中の月の日を、曜日ごとの列に並べます。
+    fn build_month_grid(&self) -> gtk::Grid {
+        let config = self.get_ext().config.borrow().clone();
+        let weekdays = (0..7)
+            .map(|i| (0..i).fold(config.week_start, |weekday, _| weekday.succ()))
+            .filter(|weekday| {
+                !config.hide_weekends
+                    || !matches!(weekday, chrono::Weekday::Sat | chrono::Weekday::Sun)
+            })
+            .collect::<Vec<chrono::Weekday>>();
+
+        let grid = gtk::GridBuilder::new()
+            .column_homogeneous(true)
+            .expand(true)
+            .build();
+        for (i, weekday) in weekdays.iter().enumerate() {
+            grid.attach(
+                &gtk::Label::new(Some(i18n::weekday_name(*weekday))),
+                i as i32,
+                0,
+                1,
+                1,
+            );
+        }
+
+        let date = self.date();
+        let first_day = chrono::NaiveDate::from_ymd(date.year(), date.month(), 1);
+        let first_week = card::compute_week_start(first_day, config.week_start);
+        let mut day = first_day;
+        while day.month() == first_day.month() {
+            if let Some(left) = weekdays
+                .iter()
+                .position(|weekday| *weekday == day.weekday())
+            {
+                let top = (day - first_week).num_days() / 7 + 1;
+                grid.attach(&self.build_month_day(day), left as i32, top as i32, 1, 1);
+            }
+            day = day.succ();
+        }
+
+        grid
+    }
+
+    /// 月の一覧から、`date`を含む週の表示に戻ります。
+    fn show_week(&self, date: chrono::NaiveDate) {
+        self.get_ext().month_mode.set(false);
+        self.set_date(date);
+    }
+
     fn reload_root_grid(&self) {
+        // 作り直す前の行から読むので、保存を待っている変更は先に保存します。
+        self.flush_saves();
         let ext = self.get_ext();
 
         let grid = ext.widget.borrow().clone().downcast::<gtk::Grid>().unwrap();
@@ -442,37 +1357,795 @@ impl View {
             grid.remove(&child)
         }
 
-        let save_factory = std::sync::Arc::new(save_column_factory_factory(&ext.path.borrow()));
+        if ext.month_mode.get() {
+            ext.columns.borrow_mut().clear();
+            ext.known.borrow_mut().clear();
+            self.watch_files();
+
+            let vbox = gtk::BoxBuilder::new()
+                .orientation(gtk::Orientation::Vertical)
+                .expand(true)
+                .build();
+            vbox.add(&self.build_week_column());
+            let scrolled_window = gtk::ScrolledWindowBuilder::new().expand(true).build();
+            scrolled_window.add(&self.build_month_grid());
+            vbox.add(&scrolled_window);
+            grid.attach(&vbox, 0, 0, 1, 1);
+            grid.show_all();
+            return;
+        }
 
+        let save_factory = rc::Rc::new(save_column_factory_factory(self));
+        ext.known.borrow_mut().clear();
+
+        let config = ext.config.borrow().clone();
+        let dates = config.visible_dates(self.date());
+        // 見出しを最初に置き、その後に日を並べます。
+        let cells = dates.len() + 1;
+        let header = self.build_week_column();
+        let (week_notes, week_notes_column) = self.build_bucket_column(
+            BucketKey::Week(config.week_of(self.date())),
+            save_factory.clone(),
+        );
+        header.add(&week_notes);
         let scrolled_window = gtk::ScrolledWindowBuilder::new().build();
-        scrolled_window.add(&self.build_week_column());
-        grid.attach(&scrolled_window, 0, 0, 1, 1);
+        scrolled_window.add(&header);
+        scrolled_window.style_context().add_class("day-column");
+        let (left, top) = config.layout.position(0, cells);
+        grid.attach(&scrolled_window, left, top, 1, 1);
+
+        let mut columns = vec![];
+        for (i, date) in dates.into_iter().enumerate() {
+            let (left, top) = config.layout.position(i + 1, cells);
+            let (widget, column) =
+                self.build_bucket_column(BucketKey::Day(date), save_factory.clone());
+            grid.attach(&widget, left, top, 1, 1);
+            columns.push(column);
+        }
+        columns.push(week_notes_column);
+        for column in &columns {
+            self.set_drop_target(column);
+        }
+        ext.columns.replace(columns);
+        self.watch_files();
 
-        let sunday = compute_last_monday(*ext.date.borrow());
-        for (i, (left, top)) in vec![(1, 0), (2, 0), (3, 0), (0, 1), (1, 1), (2, 1), (3, 1)]
-            .iter()
-            .enumerate()
-        {
-            let column = build_column(
-                self.load_daily_bucket(sunday + chrono::Duration::days(i as i64)),
-                save_factory.clone(),
+        grid.show_all();
+    }
+
+    /// `key`のファイルを読み込んで、日か週のメモを作ります。
+    /// 保存できていないカードがあれば、ファイルの代わりに表示します。読み込めなければ、編集できなくします。
+    fn build_bucket_column(
+        &self,
+        key: BucketKey,
+        save_factory: rc::Rc<SaveFactory>,
+    ) -> (gtk::Box, Column) {
+        let ext = self.get_ext();
+        let unsaved = ext.unsaved.borrow().get(&key).cloned();
+        let (cards, error) = match self.load_cards(key) {
+            Ok(cards) => {
+                ext.known.borrow_mut().insert(key, cards.clone());
+                (unsaved.clone().unwrap_or(cards), None)
+            }
+            // 保存できていないカードは、読み込めなくても見えるように残しておきます。
+            Err(error) => (unsaved.clone().unwrap_or_default(), Some(error)),
+        };
+        let (widget, column) = build_column(key, cards, ext.keys.borrow().clone(), save_factory);
+        if let Some(error) = error {
+            log::error!("読み込めませんでした: {}", error);
+            // 読み込めなかったファイルを空のカードで上書きしないように、編集できなくします。
+            column.list_box.set_sensitive(false);
+            let reload_button = gtk::Button::with_label(tr("再読み込み"));
+            reload_button.connect_clicked(glib::clone!(@weak self as this => move |_| {
+                this.reload_root_grid();
+            }));
+            show_column_message(
+                &column.info_bar,
+                gtk::MessageType::Error,
+                &i18n::format(
+                    "読み込めませんでした。ファイルを直してから、再読み込みしてください。\n{}",
+                    &[&error],
+                ),
+                &[reload_button],
+            );
+        } else if unsaved.is_some() {
+            self.show_unsaved(
+                &column.info_bar,
+                key,
+                tr("保存されていない変更があります。"),
             );
-            grid.attach(&column, *left, *top, 1, 1);
         }
 
-        grid.show_all();
+        (widget, column)
     }
 
     fn get_ext(&self) -> &ViewExt {
         ViewExt::from_instance(self)
     }
 
-    pub fn new(path: &str) -> Self {
-        let this: Self = glib::Object::new(&[("path", &path)])
-            .expect("assorted_card::Viewの作成に失敗しました。");
+    /// 表示している週に含まれる日付を返します。
+    pub fn date(&self) -> chrono::NaiveDate {
+        *self.get_ext().date.borrow()
+    }
+
+    pub fn set_date(&self, date: chrono::NaiveDate) {
+        self.get_ext().date.replace(date);
+        self.reload_root_grid();
+    }
+
+    /// 表示する日数の`periods`倍だけ、月の一覧なら`periods`か月だけ、前後に移ります。
+    fn move_period(&self, periods: i64) {
+        let date = self.date();
+        if self.get_ext().month_mode.get() {
+            let months = date.year() as i64 * 12 + date.month0() as i64 + periods;
+            let month = chrono::NaiveDate::from_ymd(
+                months.div_euclid(12) as i32,
+                months.rem_euclid(12) as u32 + 1,
+                1,
+            );
+            self.set_date(month);
+            return;
+        }
+        let days = self.get_ext().config.borrow().days() as i64;
+        self.set_date(date + chrono::Duration::days(days * periods));
+    }
+
+    /// フォーカスを持っている日の、`columns`での位置を返します。
+    fn focused_column_index(&self) -> Option<usize> {
+        let focus = self.toplevel()?.downcast::<gtk::Window>().ok()?.focus()?;
+        self.get_ext()
+            .columns
+            .borrow()
+            .iter()
+            .position(|column| focus.is_ancestor(&column.list_box))
+    }
+
+    fn focus_card_text(list_box: &gtk::ListBox, last: bool) {
+        let children = list_box.children();
+        let row = if last {
+            children.last()
+        } else {
+            children.first()
+        };
+        if let Some(text_view) = row.and_then(find_card_text) {
+            text_view.grab_focus();
+        }
+    }
+
+    /// フォーカスを持っている日、無ければ今日、今日を表示していなければ最初の日の、末尾の空のカードにフォーカスを移します。
+    fn focus_new_card(&self) {
+        let index = self.focused_column_index().or_else(|| {
+            let today = chrono::Local::today().naive_local();
+            self.get_ext()
+                .columns
+                .borrow()
+                .iter()
+                .position(|column| column.key == BucketKey::Day(today))
+        });
+        if let Some(column) = self.get_ext().columns.borrow().get(index.unwrap_or(0)) {
+            Self::focus_card_text(&column.list_box, true);
+        }
+    }
+
+    /// フォーカスを持っている日から`offset`日離れた日の、最初のカードにフォーカスを移します。
+    fn focus_column_by_offset(&self, offset: i64) {
+        let columns = self.get_ext().columns.borrow();
+        if columns.is_empty() {
+            return;
+        }
+        let index = match self.focused_column_index() {
+            Some(index) => (index as i64 + offset).rem_euclid(columns.len() as i64) as usize,
+            None => 0,
+        };
+        Self::focus_card_text(&columns[index].list_box, false);
+    }
+
+    fn add_actions(&self) {
+        let group = gio::SimpleActionGroup::new();
+        let add_action = |name: &str, activate: fn(&Self)| {
+            let action = gio::SimpleAction::new(name, None);
+            action.connect_activate(glib::clone!(@weak self as this => move |_, _| {
+                activate(&this);
+            }));
+            group.add_action(&action);
+        };
+        add_action("previous-week", |this| this.move_period(-1));
+        add_action("next-week", |this| this.move_period(1));
+        add_action("this-week", |this| {
+            this.set_date(chrono::Local::today().naive_local())
+        });
+        add_action("new-card", Self::focus_new_card);
+        add_action("next-column", |this| this.focus_column_by_offset(1));
+        add_action("previous-column", |this| this.focus_column_by_offset(-1));
+        add_action("carry-over", Self::carry_over);
+        add_action("search", Self::toggle_search);
+        add_action("undo", Self::undo);
+        add_action("redo", Self::redo);
+        self.insert_action_group("assorted-card", Some(&group));
+    }
 
+    /// `date`の最後にカードを追加して保存し、表示し直します。
+    pub fn add_card(&self, date: chrono::NaiveDate, text: &str) {
+        // 保存を待っている変更が、後から追加したカードの無い内容で上書きしないように、先に保存します。
+        self.flush_saves();
+        let card = {
+            let keys = &self.get_ext().keys.borrow().keys;
+            Card::new(keys.first().symbol.clone(), text.to_string(), keys)
+        };
+        // 保存できていないカードがあれば、それに追加します。
+        let key = BucketKey::Day(date);
+        let unsaved = self.get_ext().unsaved.borrow().get(&key).cloned();
+        let cards = match unsaved {
+            Some(cards) => Ok(cards),
+            None => self.load_cards(key),
+        };
+        match cards {
+            Ok(mut cards) => {
+                cards.push(card);
+                self.save_cards(key, cards);
+            }
+            Err(error) => log::error!("カードを追加できませんでした: {}: {}", error, card.text),
+        }
+        self.reload_root_grid();
+    }
+
+    /// カードを受け取れるように、日と週のメモにドラッグの行き先を設定します。
+    fn set_drop_target(&self, column: &Column) {
+        let list_box = &column.list_box;
+        list_box.drag_dest_set(
+            gtk::DestDefaults::ALL,
+            &drag_targets(),
+            gdk::DragAction::MOVE,
+        );
+        let key = column.key;
+        list_box.connect_drag_data_received(
+            glib::clone!(@weak self as this => move |list_box, _, _, y, selection_data, _, _| {
+                let id = match selection_data
+                    .text()
+                    .and_then(|id| uuid::Uuid::parse_str(&id).ok())
+                {
+                    Some(id) => id,
+                    None => return,
+                };
+                // 落とした位置の行の、上半分なら前に、下半分なら後ろに入れます。
+                let index = match list_box.row_at_y(y) {
+                    Some(row) => {
+                        let allocation = row.allocation();
+                        let index = row.index() as usize;
+                        if y > allocation.y + allocation.height / 2 {
+                            index + 1
+                        } else {
+                            index
+                        }
+                    }
+                    None => list_box.children().len(),
+                };
+                // ドラッグ元の行をドラッグ中に消さないように、終わってから動かします。
+                glib::idle_add_local(glib::clone!(@weak this => @default-return glib::Continue(false), move || {
+                    this.move_card(id, key, index);
+                    glib::Continue(false)
+                }));
+            }),
+        );
+    }
+
+    /// カード`id`を、`key`の`index`番目に動かします。動かした元と先をまとめて元に戻せるように記録し、
+    /// 編集と同じように`schedule_save`で保存します。
+    fn move_card(&self, id: uuid::Uuid, key: BucketKey, index: usize) {
+        // 動かす前の変更を、動かした変更とは別に元に戻せるようにします。
+        self.flush_saves();
+        let source = self.get_ext().columns.borrow().iter().find_map(|column| {
+            let cards = read_cards(&column.list_box);
+            let position = cards.iter().position(|card| card.id == id)?;
+            Some((column.key, cards, position))
+        });
+        let (source_key, mut source_cards, position) = match source {
+            Some(source) => source,
+            None => return,
+        };
+        let source_before = source_cards.clone();
+        let mut card = source_cards.remove(position);
+
+        let step = if source_key == key {
+            let index = if position < index { index - 1 } else { index };
+            source_cards.insert(index.min(source_cards.len()), card);
+            vec![Change {
+                key,
+                before: source_before,
+                after: source_cards,
+            }]
+        } else {
+            let mut cards = match self.column_cards(key) {
+                Some(cards) => cards,
+                None => return,
+            };
+            let before = cards.clone();
+            card.updated_at = chrono::Local::now();
+            cards.insert(index.min(cards.len()), card);
+            // 途中で失敗してもカードを失わないように、動かした先から保存します。
+            vec![
+                Change {
+                    key,
+                    before,
+                    after: cards,
+                },
+                Change {
+                    key: source_key,
+                    before: source_before,
+                    after: source_cards,
+                },
+            ]
+        };
+
+        // 保存の失敗や競合の表示を消さないように、表示し直してから保存します。
+        for change in &step {
+            let (key, cards) = (change.key, change.after.clone());
+            self.replace_cards(key, cards.clone());
+            self.schedule_save(
+                key,
+                Box::new(glib::clone!(@weak self as this => move || {
+                    this.save_cards(key, cards.clone());
+                })),
+            );
+        }
+        self.push_undo(step);
+    }
+
+    /// 検索バーの入力や、キーの選択が変わったら探し直します。
+    fn connect_search(&self) {
+        let search = &self.get_ext().search;
+        search.key.append(Some(""), tr("すべてのキー"));
+        for key in self.get_ext().keys.borrow().keys.iter() {
+            search.key.append(Some(&key.symbol), &key.symbol);
+        }
+        search.key.set_active_id(Some(""));
+
+        search
+            .entry
+            .connect_search_changed(glib::clone!(@weak self as this => move |_| {
+                this.search_cards();
+            }));
+        search
+            .key
+            .connect_changed(glib::clone!(@weak self as this => move |_| {
+                this.search_cards();
+            }));
+        search.bar.connect_search_mode_enabled_notify(
+            glib::clone!(@weak self as this => move |bar| {
+                let scrolled_window = &this.get_ext().search.scrolled_window;
+                if bar.is_search_mode() {
+                    scrolled_window.show_all();
+                    this.search_cards();
+                } else {
+                    scrolled_window.hide();
+                }
+            }),
+        );
+        search
+            .results
+            .connect_row_activated(glib::clone!(@weak self as this => move |_, row| {
+                let hit = this.get_ext().search_hits.borrow().get(row.index() as usize).copied();
+                if let Some((date, id)) = hit {
+                    this.show_week(date);
+                    this.focus_card(id);
+                }
+            }));
+    }
+
+    /// ルートの下のすべての日から、検索バーの条件に合うカードを探して並べます。
+    fn search_cards(&self) {
+        let ext = self.get_ext();
+        let search = &ext.search;
+        for child in search.results.children() {
+            search.results.remove(&child);
+        }
+        ext.search_hits.borrow_mut().clear();
+        if !search.bar.is_search_mode() {
+            return;
+        }
+
+        let root = ext.path.borrow().clone();
+        let mut index = ext.index.borrow_mut();
+        if let Err(error) = index.refresh(&root) {
+            log::error!("検索のためにカードを読み込めませんでした: {}", error);
+        }
+        let key = search.key.active_id().filter(|key| !key.is_empty());
+        let query = search.entry.text();
+        // 多すぎると表示が重くなるので、新しい方から絞ります。
+        let hits = index.search(&query, key.as_deref());
+        for (date, card) in hits.iter().take(200) {
+            let hbox = gtk::BoxBuilder::new()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(8)
+                .build();
+            hbox.add(&gtk::Label::new(Some(&format!(
+                "{} ({})",
+                date.format("%Y-%m-%d"),
+                i18n::weekday_name(date.weekday())
+            ))));
+            hbox.add(&gtk::Label::new(Some(&card.key)));
+            hbox.add(
+                &gtk::LabelBuilder::new()
+                    .label(card.text.lines().next().unwrap_or(""))
+                    .ellipsize(gtk::pango::EllipsizeMode::End)
+                    .xalign(0.0)
+                    .build(),
+            );
+            search.results.add(&hbox);
+            ext.search_hits.borrow_mut().push((*date, card.id));
+        }
+        search.results.show_all();
+    }
+
+    fn toggle_search(&self) {
+        let search = &self.get_ext().search;
+        let enabled = !search.bar.is_search_mode();
+        search.bar.set_search_mode(enabled);
+        if enabled {
+            search.entry.grab_focus();
+        }
+    }
+
+    /// 表示中の週にあるカード`id`の本文にフォーカスを移します。
+    fn focus_card(&self, id: uuid::Uuid) {
+        for column in self.get_ext().columns.borrow().iter() {
+            for child in column.list_box.children() {
+                if find_card(&child).map(|row| row.card_id()) == Some(id) {
+                    if let Some(text_view) = find_card_text(&child) {
+                        text_view.grab_focus();
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 前の日の終わっていない作業のカードを今日に持ち越して、表示し直します。
+    fn carry_over(&self) {
+        self.carry_over_since(None);
+    }
+
+    /// 今日まだ持ち越していなければ、前に持ち越した日から後の日のカードを持ち越します。
+    /// 設定ファイルを読み込み直すたびにビューを作り直すので、作るたびには持ち越しません。
+    fn auto_carry_over(&self) {
+        let today = chrono::Local::today().naive_local();
+        let last_carry_over = self.get_ext().last_carry_over.get();
+        if last_carry_over == Some(today) {
+            return;
+        }
+        let since =
+            last_carry_over.unwrap_or_else(|| today - chrono::Duration::days(AUTO_CARRY_OVER_DAYS));
+        self.carry_over_since(Some(since));
+    }
+
+    /// `since`の日から、無ければすべての日から、今日に持ち越します。
+    fn carry_over_since(&self, since: Option<chrono::NaiveDate>) {
+        // 入力中のカードを先に保存しておかないと、持ち越しで書き換えたファイルと食い違います。
+        self.flush_saves();
+
+        let config = self.get_ext().config.borrow().clone();
+        let root = self.get_ext().path.borrow().clone();
+        let today = chrono::Local::today().naive_local();
+        match card::carry_over(
+            &root,
+            today,
+            since,
+            config.carry_over.mode,
+            &config.keys,
+            config.backups,
+        ) {
+            Ok(count) => {
+                log::info!("{}件のカードを持ち越しました。", count);
+                self.get_ext().last_carry_over.set(Some(today));
+            }
+            Err(error) => log::error!("カードを持ち越せませんでした: {}", error),
+        }
+        self.reload_root_grid();
+    }
+
+    /// `config.root`は展開済みのものを渡してください。
+    pub fn new(config: Config) -> Self {
+        let this: Self = glib::Object::new(&[("path", &config.root)])
+            .unwrap_or_else(|_| panic!("{}", tr("assorted_card::Viewの作成に失敗しました。")));
+        let auto_carry_over = config.carry_over.auto;
+        this.get_ext()
+            .keys
+            .replace(rc::Rc::new(Keys::new(config.keys.clone())));
+        this.get_ext().config.replace(config);
+
+        this.add_actions();
+        this.connect_search();
         this.reload_root_grid();
+        if auto_carry_over {
+            // 前に持ち越した日を`restore_state`で受け取ってから持ち越します。
+            glib::idle_add_local(
+                glib::clone!(@weak this => @default-return glib::Continue(false), move || {
+                    this.auto_carry_over();
+                    glib::Continue(false)
+                }),
+            );
+        }
 
         this
     }
 }
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub root: String,
+    /// 週の最初の曜日です。
+    #[serde(default = "default_week_start")]
+    pub week_start: chrono::Weekday,
+    /// 並べる日数です。7の倍数なら週の最初の曜日から、そうでなければ表示する日から並べます。
+    #[serde(default = "default_days")]
+    pub days: u32,
+    /// 土曜日と日曜日を表示しないかです。
+    #[serde(default)]
+    pub hide_weekends: bool,
+    #[serde(default)]
+    pub layout: Layout,
+    /// カードを保存するときに残しておく以前の内容の世代数です。0なら残しません。
+    #[serde(default)]
+    pub backups: usize,
+    #[serde(default)]
+    pub carry_over: CarryOverConfig,
+    #[serde(default)]
+    pub keys: card::CardKeys,
+}
+
+fn default_week_start() -> chrono::Weekday {
+    chrono::Weekday::Mon
+}
+
+fn default_days() -> u32 {
+    7
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            root: String::new(),
+            week_start: default_week_start(),
+            days: default_days(),
+            hide_weekends: false,
+            layout: Layout::default(),
+            backups: 0,
+            carry_over: CarryOverConfig::default(),
+            keys: card::CardKeys::default(),
+        }
+    }
+}
+
+impl Config {
+    /// 並べる日数です。0が指定されていても、1日は表示します。
+    pub fn days(&self) -> u32 {
+        self.days.max(1)
+    }
+
+    /// `date`を表示するときに、最初に並べる日です。
+    pub fn first_date(&self, date: chrono::NaiveDate) -> chrono::NaiveDate {
+        if self.days() % 7 == 0 {
+            card::compute_week_start(date, self.week_start)
+        } else {
+            date
+        }
+    }
+
+    /// `date`を含む週です。週の最初の曜日によらず、ISO 8601の週の数え方に合わせます。
+    pub fn week_of(&self, date: chrono::NaiveDate) -> chrono::IsoWeek {
+        (card::compute_week_start(date, self.week_start) + chrono::Duration::days(3)).iso_week()
+    }
+
+    /// `date`を表示するときに並べる日です。
+    pub fn visible_dates(&self, date: chrono::NaiveDate) -> Vec<chrono::NaiveDate> {
+        let first_date = self.first_date(date);
+        (0..self.days())
+            .map(|i| first_date + chrono::Duration::days(i as i64))
+            .filter(|date| {
+                !self.hide_weekends
+                    || !matches!(date.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun)
+            })
+            .collect()
+    }
+}
+
+/// 見出しと日の並べ方です。
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// 1行に4つまでの格子に並べます。
+    Grid,
+    /// 横1列に並べます。
+    Row,
+    /// 縦1列に並べます。
+    Column,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::Grid
+    }
+}
+
+impl Layout {
+    /// `cells`個を並べるときの、`index`番目の位置(左, 上)です。
+    fn position(self, index: usize, cells: usize) -> (i32, i32) {
+        let (left, top) = match self {
+            Layout::Row => (index, 0),
+            Layout::Column => (0, index),
+            Layout::Grid => {
+                let rows = (cells + 3) / 4;
+                let columns = (cells + rows - 1) / rows;
+                (index % columns, index / columns)
+            }
+        };
+        (left as i32, top as i32)
+    }
+}
+
+/// 前の日の終わっていない作業のカードを今日に持ち越すときの設定です。
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CarryOverConfig {
+    /// 開いたときに持ち越すかです。持ち越さない場合も、ショートカットで持ち越せます。
+    pub auto: bool,
+    pub mode: card::CarryOverMode,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct State {
+    date: chrono::NaiveDate,
+    /// 最後に持ち越した日です。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_carry_over: Option<chrono::NaiveDate>,
+}
+
+pub struct Factory;
+
+impl super::ViewFactory for Factory {
+    type Config = Config;
+
+    fn build(&self, mut config: Self::Config) -> gtk::Widget {
+        config.root = crate::libs::expand_path(&config.root);
+        View::new(config).upcast()
+    }
+
+    fn save_state(&self, widget: &gtk::Widget) -> Option<toml::Value> {
+        let view = widget.downcast_ref::<View>()?;
+        toml::Value::try_from(State {
+            date: view.date(),
+            last_carry_over: view.get_ext().last_carry_over.get(),
+        })
+        .ok()
+    }
+
+    fn restore_state(&self, widget: &gtk::Widget, state: toml::Value) {
+        if let (Some(view), Ok(state)) = (widget.downcast_ref::<View>(), state.try_into::<State>())
+        {
+            view.get_ext().last_carry_over.set(state.last_carry_over);
+            view.set_date(state.date);
+        }
+    }
+
+    fn flush(&self, widget: &gtk::Widget) {
+        if let Some(view) = widget.downcast_ref::<View>() {
+            view.flush_saves();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> chrono::NaiveDate {
+        chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn dates(texts: &[&str]) -> Vec<chrono::NaiveDate> {
+        texts.iter().map(|text| date(text)).collect()
+    }
+
+    #[test]
+    fn visible_dates_start_at_week_start() {
+        let config = Config::default();
+
+        assert_eq!(
+            config.visible_dates(date("2026-10-18")),
+            dates(&[
+                "2026-10-12",
+                "2026-10-13",
+                "2026-10-14",
+                "2026-10-15",
+                "2026-10-16",
+                "2026-10-17",
+                "2026-10-18",
+            ])
+        );
+    }
+
+    #[test]
+    fn visible_dates_start_at_date_unless_whole_weeks() {
+        let config = Config {
+            days: 3,
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.visible_dates(date("2026-10-18")),
+            dates(&["2026-10-18", "2026-10-19", "2026-10-20"])
+        );
+    }
+
+    #[test]
+    fn visible_dates_show_at_least_one_day() {
+        let config = Config {
+            days: 0,
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.visible_dates(date("2026-10-18")),
+            dates(&["2026-10-18"])
+        );
+    }
+
+    #[test]
+    fn visible_dates_hide_weekends() {
+        let config = Config {
+            week_start: chrono::Weekday::Sun,
+            hide_weekends: true,
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.visible_dates(date("2026-10-18")),
+            dates(&[
+                "2026-10-19",
+                "2026-10-20",
+                "2026-10-21",
+                "2026-10-22",
+                "2026-10-23",
+            ])
+        );
+    }
+
+    #[test]
+    fn week_of_follows_iso_weeks() {
+        let monday = Config::default();
+        let sunday = Config {
+            week_start: chrono::Weekday::Sun,
+            ..Config::default()
+        };
+
+        assert_eq!(monday.week_of(date("2026-10-18")).week(), 42);
+        assert_eq!(monday.week_of(date("2026-10-19")).week(), 43);
+        assert_eq!(sunday.week_of(date("2026-10-18")).week(), 43);
+        assert_eq!(sunday.week_of(date("2026-10-17")).week(), 42);
+        // 年をまたぐ週は、木曜日のある年の週です。
+        let week = monday.week_of(date("2026-01-01"));
+        assert_eq!((week.year(), week.week()), (2026, 1));
+    }
+
+    #[test]
+    fn position_in_row_and_column() {
+        assert_eq!(Layout::Row.position(2, 8), (2, 0));
+        assert_eq!(Layout::Column.position(2, 8), (0, 2));
+    }
+
+    #[test]
+    fn position_in_grid_balances_rows() {
+        let positions = |cells: usize| {
+            (0..cells)
+                .map(|index| Layout::Grid.position(index, cells))
+                .collect::<Vec<(i32, i32)>>()
+        };
+
+        assert_eq!(positions(1), vec![(0, 0)]);
+        assert_eq!(positions(4), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+        // 5つは3つと2つに分けます。
+        assert_eq!(positions(5), vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1)]);
+        assert_eq!(positions(8)[4], (0, 1));
+        assert_eq!(positions(9)[3], (0, 1));
+    }
+}
@@ -5,6 +5,9 @@ use std::cell;
 use std::fs;
 use std::io::prelude::*;
 use std::path;
+
+use crate::i18n::tr;
+
 enum FileType {
     Directory,
     File,
@@ -26,10 +29,9 @@ fn list_files<P: AsRef<path::Path>>(path: P) -> Vec<FileEntry> {
             };
             FileEntry {
                 type_,
-                name: entry
-                    .file_name()
-                    .into_string()
-                    .expect("扱えない名前を持ったファイルがあります。"),
+                name: entry.file_name().into_string().unwrap_or_else(|_| {
+                    panic!("{}", tr("扱えない名前を持ったファイルがあります。"))
+                }),
             }
         })
         .collect()
@@ -38,6 +40,10 @@ fn list_files<P: AsRef<path::Path>>(path: P) -> Vec<FileEntry> {
 pub struct ViewExt {
     widget: cell::RefCell<gtk::Paned>,
     path: cell::RefCell<Option<String>>,
+    search_entry: gtk::SearchEntry,
+    list_box: cell::RefCell<Option<gtk::ListBox>>,
+    /// 開いているファイルのパスと、その内容を編集しているTextViewです。
+    opened_file: cell::RefCell<Option<(String, gtk::TextView)>>,
 }
 
 #[glib::object_subclass]
@@ -56,6 +62,11 @@ impl ObjectSubclass for ViewExt {
         Self {
             widget: cell::RefCell::new(panel),
             path: cell::RefCell::new(None),
+            search_entry: gtk::SearchEntryBuilder::new()
+                .placeholder_text(tr("ファイルを検索"))
+                .build(),
+            list_box: cell::RefCell::new(None),
+            opened_file: cell::RefCell::new(None),
         }
     }
 }
@@ -102,14 +113,16 @@ impl ObjectImpl for ViewExt {
 
 glib::wrapper! {
     pub struct View(ObjectSubclass<ViewExt>)
-        @extends gtk::Widget, gtk::Container, gtk::Bin, gtk::Window, gtk::ApplicationWindow;
+        @extends gtk::Widget, gtk::Container, gtk::Bin;
 }
 
 impl View {
     pub fn new(path: &str) -> Self {
         let this: Self = glib::Object::new(&[("path", &path)])
-            .expect("files_and_file::Viewの作成に失敗しました。");
+            .unwrap_or_else(|_| panic!("{}", tr("files_and_file::Viewの作成に失敗しました。")));
 
+        this.connect_search_entry();
+        this.add_actions();
         this.reload_files();
 
         this
@@ -119,6 +132,14 @@ impl View {
         ViewExt::from_instance(self)
     }
 
+    pub fn paned_position(&self) -> i32 {
+        self.get_paned().position()
+    }
+
+    pub fn set_paned_position(&self, position: i32) {
+        self.get_paned().set_position(position);
+    }
+
     fn get_paned(&self) -> gtk::Paned {
         let ext = self.get_ext();
         let a = ext.widget.borrow();
@@ -126,15 +147,19 @@ impl View {
         a.clone()
     }
 
+    fn go_parent(&self) {
+        {
+            let mut p = self.get_ext().path.borrow_mut();
+            let new_path = format!("{}{}{}", p.as_ref().unwrap(), path::MAIN_SEPARATOR, "..");
+            p.replace(new_path);
+        }
+        self.reload_files();
+    }
+
     fn build_go_parent_button(&self) -> gtk::Button {
         let go_parent_button = gtk::Button::with_label("D ..");
         go_parent_button.connect_clicked(glib::clone!(@weak self as this => move |_| {
-            {
-                let mut p = this.get_ext().path.borrow_mut();
-                let new_path = format!("{}{}{}", p.as_ref().unwrap(), path::MAIN_SEPARATOR, "..");
-                p.replace(new_path);
-            }
-            this.reload_files();
+            this.go_parent();
         }));
         let label = go_parent_button
             .child()
@@ -178,16 +203,82 @@ impl View {
             list_box.add(&button);
         }
         list_box.set_selection_mode(gtk::SelectionMode::None);
+        list_box.set_filter_func(Some(Box::new(
+            glib::clone!(@weak self as this => @default-return true, move |row| {
+                this.matches_search(row)
+            }),
+        )));
 
         list_box
     }
 
-    fn replace_paned_child1(&self, scrolled_window: &gtk::ScrolledWindow) {
+    /// ファイラーの行が検索文字列を含むかを返します。親ディレクトリへ移動する行は常に表示します。
+    fn matches_search(&self, row: &gtk::ListBoxRow) -> bool {
+        if row.index() == 0 {
+            return true;
+        }
+        let query = self.get_ext().search_entry.text().to_lowercase();
+        let label = row
+            .child()
+            .and_then(|child| child.downcast::<gtk::Button>().ok())
+            .and_then(|button| button.label())
+            .map(|label| label.to_lowercase())
+            .unwrap_or_default();
+
+        // 先頭の"D "や"F "は検索の対象にしません。
+        label.get(2..).unwrap_or("").contains(&query)
+    }
+
+    fn connect_search_entry(&self) {
+        let search_entry = &self.get_ext().search_entry;
+        search_entry.connect_search_changed(glib::clone!(@weak self as this => move |_| {
+            let list_box = this.get_ext().list_box.borrow().clone();
+            if let Some(list_box) = list_box {
+                list_box.invalidate_filter();
+            }
+        }));
+        // Enterで、検索に一致した最初のファイルかディレクトリを開きます。
+        search_entry.connect_activate(glib::clone!(@weak self as this => move |_| {
+            let list_box = this.get_ext().list_box.borrow().clone();
+            let first_match = list_box.and_then(|list_box| {
+                list_box
+                    .children()
+                    .into_iter()
+                    .filter_map(|child| child.downcast::<gtk::ListBoxRow>().ok())
+                    .skip(1)
+                    .find(|row| this.matches_search(row))
+            });
+            if let Some(button) = first_match
+                .and_then(|row| row.child())
+                .and_then(|child| child.downcast::<gtk::Button>().ok())
+            {
+                button.clicked();
+            }
+        }));
+        search_entry.connect_stop_search(|search_entry| search_entry.set_text(""));
+    }
+
+    fn add_actions(&self) {
+        let group = gio::SimpleActionGroup::new();
+        let add_action = |name: &str, activate: fn(&Self)| {
+            let action = gio::SimpleAction::new(name, None);
+            action.connect_activate(glib::clone!(@weak self as this => move |_, _| {
+                activate(&this);
+            }));
+            group.add_action(&action);
+        };
+        add_action("save", Self::save_file);
+        add_action("go-parent", Self::go_parent);
+        add_action("search", |this| this.get_ext().search_entry.grab_focus());
+        self.insert_action_group("files-and-file", Some(&group));
+    }
+
+    fn replace_paned_child1(&self, widget: &gtk::Box) {
         let paned = self.get_paned();
         if let Some(widget) = paned.child1() {
             paned.remove(&widget);
         }
-        paned.add1(scrolled_window);
+        paned.add1(widget);
         paned.show_all();
     }
 
@@ -203,15 +294,27 @@ impl View {
 
     /// ファイラーを現在のパスの現在の状態に合わせます。
     fn reload_files(&self) {
-        let path_ref = self.get_ext().path.borrow();
-        let list_box = self.build_filer(path_ref.as_ref().unwrap());
+        let ext = self.get_ext();
+        let list_box = self.build_filer(ext.path.borrow().as_ref().unwrap());
         let scrolled_window =
             gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
         scrolled_window.add(&list_box);
-        self.replace_paned_child1(&scrolled_window);
+        ext.list_box.replace(Some(list_box));
+
+        if let Some(parent) = ext.search_entry.parent() {
+            parent
+                .downcast::<gtk::Container>()
+                .unwrap()
+                .remove(&ext.search_entry);
+        }
+        ext.search_entry.set_text("");
+        let vbox = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        vbox.add(&ext.search_entry);
+        vbox.pack_start(&scrolled_window, true, true, 0);
+        self.replace_paned_child1(&vbox);
     }
 
-    fn open_file(&self, path: &str) {
+    pub fn open_file(&self, path: &str) {
         let content = fs::read_to_string(path).unwrap();
         let scrolled_window =
             gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
@@ -221,22 +324,66 @@ impl View {
         scrolled_window.add(&text_view);
 
         let vbox = gtk::Box::new(gtk::Orientation::Vertical, 0);
-        let save_button = gtk::Button::with_label("保存");
+        let save_button = gtk::Button::with_label(tr("保存"));
+        save_button.connect_clicked(glib::clone!(@weak self as this => move |_| {
+            this.save_file();
+        }));
+        self.get_ext()
+            .opened_file
+            .replace(Some((path.to_string(), text_view)));
+
+        vbox.add(&save_button);
+        vbox.pack_start(&scrolled_window, true, true, 0);
+
+        self.replace_paned_child2(&vbox);
+    }
 
-        let p = path.to_string();
-        save_button.connect_clicked(move |_| {
+    /// 開いているファイルを保存します。
+    pub fn save_file(&self) {
+        if let Some((ref p, ref text_view)) = *self.get_ext().opened_file.borrow() {
             let buffer = text_view.buffer().unwrap();
             let (start, end) = buffer.bounds();
             let text = buffer.text(&start, &end, false).unwrap().to_string();
             log::debug!("保存内容: {}", text);
-            let mut file = fs::File::create(&p).unwrap();
+            let mut file = fs::File::create(p).unwrap();
             file.write_all(text.as_bytes()).unwrap();
             file.flush().unwrap();
-        });
+        }
+    }
+}
 
-        vbox.add(&save_button);
-        vbox.pack_start(&scrolled_window, true, true, 0);
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub root: String,
+}
 
-        self.replace_paned_child2(&vbox);
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct State {
+    paned_position: i32,
+}
+
+pub struct Factory;
+
+impl super::ViewFactory for Factory {
+    type Config = Config;
+
+    fn build(&self, config: Self::Config) -> gtk::Widget {
+        View::new(&crate::libs::expand_path(&config.root)).upcast()
+    }
+
+    fn save_state(&self, widget: &gtk::Widget) -> Option<toml::Value> {
+        let view = widget.downcast_ref::<View>()?;
+        toml::Value::try_from(State {
+            paned_position: view.paned_position(),
+        })
+        .ok()
+    }
+
+    fn restore_state(&self, widget: &gtk::Widget, state: toml::Value) {
+        if let (Some(view), Ok(state)) = (widget.downcast_ref::<View>(), state.try_into::<State>())
+        {
+            view.set_paned_position(state.paned_position);
+        }
     }
 }
@@ -1,2 +1,178 @@
+use std::collections;
+use std::fmt;
+
+use crate::i18n;
+
 pub mod assorted_card;
 pub mod files_and_file;
+
+/// ビューの作り方を表します。`Config`は設定ファイルの`views[].config`から読み込まれます。
+pub trait ViewFactory {
+    type Config: serde::de::DeserializeOwned;
+
+    fn build(&self, config: Self::Config) -> gtk::Widget;
+
+    /// 次の起動時に復元したい状態を返します。`widget`はこのファクトリーが作ったものです。
+    fn save_state(&self, _widget: &gtk::Widget) -> Option<toml::Value> {
+        None
+    }
+
+    fn restore_state(&self, _widget: &gtk::Widget, _state: toml::Value) {}
+
+    /// ウィンドウを閉じる前や作り直す前に呼ばれます。保存を待っている変更があれば、すぐに保存します。
+    fn flush(&self, _widget: &gtk::Widget) {}
+}
+
+#[derive(Debug)]
+pub enum BuildError {
+    UnknownComponent(String),
+    InvalidConfig(toml::de::Error),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::UnknownComponent(component) => {
+                write!(
+                    f,
+                    "{}",
+                    i18n::format("不明なコンポーネントです: {}", &[component])
+                )
+            }
+            BuildError::InvalidConfig(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// `ViewFactory`の型引数を消して、レジストリに並べられるようにします。
+trait AnyViewFactory {
+    fn parse(&self, config: &toml::Value) -> Result<(), toml::de::Error>;
+    fn build(&self, config: &toml::Value) -> Result<gtk::Widget, toml::de::Error>;
+    fn save_state(&self, widget: &gtk::Widget) -> Option<toml::Value>;
+    fn restore_state(&self, widget: &gtk::Widget, state: toml::Value);
+    fn flush(&self, widget: &gtk::Widget);
+}
+
+impl<F: ViewFactory> AnyViewFactory for F {
+    fn parse(&self, config: &toml::Value) -> Result<(), toml::de::Error> {
+        config.clone().try_into::<F::Config>().map(|_| ())
+    }
+
+    fn build(&self, config: &toml::Value) -> Result<gtk::Widget, toml::de::Error> {
+        Ok(ViewFactory::build(self, config.clone().try_into()?))
+    }
+
+    fn save_state(&self, widget: &gtk::Widget) -> Option<toml::Value> {
+        ViewFactory::save_state(self, widget)
+    }
+
+    fn restore_state(&self, widget: &gtk::Widget, state: toml::Value) {
+        ViewFactory::restore_state(self, widget, state)
+    }
+
+    fn flush(&self, widget: &gtk::Widget) {
+        ViewFactory::flush(self, widget)
+    }
+}
+
+/// コンポーネント名とビューの作り方の対応表です。
+pub struct ViewRegistry {
+    factories: collections::BTreeMap<String, Box<dyn AnyViewFactory>>,
+}
+
+impl fmt::Debug for ViewRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.factories.keys()).finish()
+    }
+}
+
+impl Default for ViewRegistry {
+    /// 組み込みのビューを登録したレジストリを作ります。
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("assorted_card", assorted_card::Factory);
+        registry.register("files_and_file", files_and_file::Factory);
+
+        registry
+    }
+}
+
+impl ViewRegistry {
+    /// 何も登録していないレジストリを作ります。組み込みのビューは`default`で登録されます。
+    pub fn empty() -> Self {
+        Self {
+            factories: collections::BTreeMap::new(),
+        }
+    }
+
+    /// 同じ名前で登録済みのものがあれば置き換えます。
+    pub fn register<F: ViewFactory + 'static>(&mut self, component: &str, factory: F) {
+        self.factories
+            .insert(component.to_string(), Box::new(factory));
+    }
+
+    pub fn components(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    /// ビューを作らずに、コンポーネント名と設定だけを検証します。
+    pub fn validate(&self, component: &str, config: &toml::Value) -> Result<(), BuildError> {
+        self.factories
+            .get(component)
+            .ok_or_else(|| BuildError::UnknownComponent(component.to_string()))?
+            .parse(config)
+            .map_err(BuildError::InvalidConfig)
+    }
+
+    pub fn build(&self, component: &str, config: &toml::Value) -> Result<gtk::Widget, BuildError> {
+        self.factories
+            .get(component)
+            .ok_or_else(|| BuildError::UnknownComponent(component.to_string()))?
+            .build(config)
+            .map_err(BuildError::InvalidConfig)
+    }
+
+    pub fn save_state(&self, component: &str, widget: &gtk::Widget) -> Option<toml::Value> {
+        self.factories.get(component)?.save_state(widget)
+    }
+
+    pub fn restore_state(&self, component: &str, widget: &gtk::Widget, state: toml::Value) {
+        if let Some(factory) = self.factories.get(component) {
+            factory.restore_state(widget, state);
+        }
+    }
+
+    pub fn flush(&self, component: &str, widget: &gtk::Widget) {
+        if let Some(factory) = self.factories.get(component) {
+            factory.flush(widget);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_registers_built_in_views() {
+        assert_eq!(ViewRegistry::empty().components().count(), 0);
+        assert_eq!(
+            ViewRegistry::default().components().collect::<Vec<&str>>(),
+            vec!["assorted_card", "files_and_file"]
+        );
+    }
+
+    #[test]
+    fn validate_reports_unknown_component() {
+        let config = toml::Value::Table(toml::value::Table::new());
+
+        assert!(matches!(
+            ViewRegistry::empty().validate("assorted_card", &config),
+            Err(BuildError::UnknownComponent(_))
+        ));
+        assert!(matches!(
+            ViewRegistry::default().validate("assorted_card", &config),
+            Err(BuildError::InvalidConfig(_))
+        ));
+    }
+}